@@ -0,0 +1,84 @@
+use bytes::BytesMut;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ola::ola::proto::{DmxData, OlaServerServiceCall};
+use ola::ola::RpcContext;
+use ola::session::Session;
+use ola::DmxBuffer;
+
+/// Universes streamed per frame.
+const UNIVERSES: u32 = 40;
+
+fn stream_dmx(c: &mut Criterion) {
+    let data = DmxBuffer::from([127; 512]);
+    let mut group = c.benchmark_group("stream 40 universes");
+
+    group.bench_function("message", |b| {
+        let mut ctx = RpcContext::new();
+        b.iter(|| {
+            for universe in 0..UNIVERSES {
+                let mut dst = BytesMut::new();
+                let call = OlaServerServiceCall::StreamDmxData(DmxData {
+                    universe: universe as i32,
+                    data: data.to_vec(),
+                    priority: Some(100),
+                });
+                ctx.encode(call, &mut dst).unwrap();
+                black_box(&dst);
+            }
+        })
+    });
+
+    group.bench_function("direct", |b| {
+        let mut ctx = RpcContext::new();
+        let mut dst = BytesMut::new();
+        b.iter(|| {
+            for universe in 0..UNIVERSES {
+                dst.clear();
+                ctx.encode_stream_dmx(universe, black_box(data.as_slice()), 100, &mut dst);
+                black_box(&dst);
+            }
+        })
+    });
+
+    group.bench_function("session", |b| {
+        let mut session = Session::new();
+        b.iter(|| {
+            for universe in 0..UNIVERSES {
+                session.send_dmx(universe, black_box(&data), 100).unwrap();
+                let len = session.transmit().len();
+                black_box(session.transmit());
+                session.advance_transmit(len);
+            }
+        })
+    });
+
+    #[cfg(feature = "tokio")]
+    group.bench_function("client async", |b| {
+        use ola::client::ClientAsync;
+        use tokio::io::{copy, duplex, sink};
+        use tokio::runtime::Builder;
+
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let client = runtime.block_on(async {
+            let (stream, mut olad) = duplex(64 * 1024);
+            tokio::spawn(async move { copy(&mut olad, &mut sink()).await });
+
+            ClientAsync::from_stream(stream)
+        });
+        b.iter(|| {
+            runtime.block_on(async {
+                for universe in 0..UNIVERSES {
+                    client
+                        .send_dmx_streaming(universe, black_box(&data))
+                        .await
+                        .unwrap();
+                }
+            })
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, stream_dmx);
+criterion_main!(benches);
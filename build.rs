@@ -1,12 +1,16 @@
 use std::io::Result;
 
-use proc_macro2::TokenStream;
+use proc_macro2::{Ident, TokenStream};
 use prost_build::{Method, Service, ServiceGenerator};
 use quote::{format_ident, quote};
 
 fn main() -> Result<()> {
     prost_build::Config::new()
         .service_generator(Box::new(OlaRpcServiceGenerator::new()))
+        .type_attribute(
+            ".",
+            "#[cfg_attr(feature = \"serde\", derive(::serde::Serialize, ::serde::Deserialize))]",
+        )
         .compile_protos(
             &["ola/common/protocol/Ola.proto", "ola/common/rpc/Rpc.proto"],
             &["ola/"],
@@ -69,6 +73,20 @@ impl OlaRpcServiceGenerator {
         buf.push_str(tokens.to_string().as_str());
     }
 
+    /// Message type a method's calls are sent as. Methods without a response
+    /// are streamed.
+    fn rpc_type(&self, method: &Method) -> Ident {
+        format_ident!(
+            "{}",
+            // the type name is fully qualified (`.ola.proto.STREAMING_NO_RESPONSE`)
+            if method.output_proto_type.ends_with(".STREAMING_NO_RESPONSE") {
+                "StreamRequest"
+            } else {
+                "Request"
+            }
+        )
+    }
+
     fn generate_variant(&self, method: &Method) -> TokenStream {
         let method_name = format_ident!("{}", method.proto_name);
         let input_type = format_ident!("{}", method.input_type);
@@ -79,7 +97,7 @@ impl OlaRpcServiceGenerator {
     }
 
     fn generate_decode_impl(&self, method: &Method) -> TokenStream {
-        let rpc_type = format_ident!("Request");
+        let rpc_type = self.rpc_type(method);
         let method_name = format_ident!("{}", method.proto_name);
         let method_name_raw = method.proto_name.clone();
         let input_type = format_ident!("{}", method.input_type);
@@ -100,14 +118,7 @@ impl OlaRpcServiceGenerator {
     fn generate_encode_impl(&self, method: &Method) -> TokenStream {
         let method_name = format_ident!("{}", method.proto_name);
         let method_name_raw = method.proto_name.clone();
-        let rpc_type = format_ident!(
-            "{}",
-            if method.output_proto_type == "STREAMING_NO_RESPONSE" {
-                "StreamRequest"
-            } else {
-                "Request"
-            }
-        );
+        let rpc_type = self.rpc_type(method);
 
         quote! {
             Self::#method_name(r) => rpc::RpcMessage {
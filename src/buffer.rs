@@ -1,14 +1,22 @@
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
-use std::ops::{Deref, DerefMut, Index, IndexMut};
+use std::hash::{Hash, Hasher};
+use std::iter::FusedIterator;
+use std::ops::{Bound, Deref, DerefMut, Index, IndexMut, Range, RangeBounds, RangeInclusive};
 
-/// A fixed-size byte buffer representing the state of a single DMX universe.
+use crate::fade::Crossfade;
+
+/// The maximum number of slots (channels) in a DMX universe.
+pub(crate) const MAX_SLOTS: usize = 512;
+
+/// A byte buffer representing the state of a single DMX universe.
 ///
-/// `DmxBuffer` holds exactly 512 bytes and is stored on the heap. All OLA
-/// methods dealing with retrieving or setting universes are done with
-/// `DmxBuffer`s. Various "buffer-like" types including `[u8; 512]` and
-/// `Vec<u8>` can be converted to and from `DmxBuffer`s, but must be exactly
-/// 512 bytes long for conversion to succeed.
+/// `DmxBuffer` holds between 1 and 512 slots and is stored on the heap. New
+/// buffers span a full universe, but OLA may also send (and accept) partial
+/// universes, whose length is preserved. All OLA methods dealing with
+/// retrieving or setting universes are done with `DmxBuffer`s. Various
+/// "buffer-like" types including `[u8; 512]` and `Vec<u8>` can be converted
+/// to and from `DmxBuffer`s, as long as they hold 1 to 512 bytes.
 ///
 /// # Examples
 ///
@@ -29,17 +37,30 @@ use std::ops::{Deref, DerefMut, Index, IndexMut};
 /// assert_ne!(*universe, [0; 512]);
 /// ```
 #[derive(Clone, Debug, Eq)]
-pub struct DmxBuffer(Box<[u8; 512]>);
+pub struct DmxBuffer {
+    // slots past `slots` are always zero
+    data: Box<[u8; MAX_SLOTS]>,
+    slots: usize,
+}
 
 impl Default for DmxBuffer {
     fn default() -> Self {
-        Self(Box::new([0; 512]))
+        Self {
+            data: Box::new([0; MAX_SLOTS]),
+            slots: MAX_SLOTS,
+        }
     }
 }
 
 impl PartialEq for DmxBuffer {
     fn eq(&self, other: &Self) -> bool {
-        self.0.as_ref() == other.0.as_ref()
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Hash for DmxBuffer {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state);
     }
 }
 
@@ -53,7 +74,10 @@ impl From<[u8; 512]> for DmxBuffer {
     /// assert_eq!(DmxBuffer::from([0; 512]), DmxBuffer::new());
     /// ```
     fn from(b: [u8; 512]) -> Self {
-        Self(Box::new(b))
+        Self {
+            data: Box::new(b),
+            slots: MAX_SLOTS,
+        }
     }
 }
 
@@ -67,22 +91,29 @@ impl From<&[u8; 512]> for DmxBuffer {
     /// assert_eq!(DmxBuffer::from(&[0; 512]), DmxBuffer::new());
     /// ```
     fn from(b: &[u8; 512]) -> Self {
-        Self(Box::new(*b))
+        Self {
+            data: Box::new(*b),
+            slots: MAX_SLOTS,
+        }
     }
 }
 
 impl From<DmxBuffer> for Vec<u8> {
     /// Convert a DMX buffer into a vector by transferring ownership of the
-    /// existing heap allocation.
+    /// existing heap allocation. The vector holds one byte per slot.
     ///
     /// # Examples
     ///
     /// ```
     /// # use ola::DmxBuffer;
     /// assert_eq!(Vec::<u8>::from(DmxBuffer::new()), vec![0; 512]);
+    /// assert_eq!(Vec::<u8>::from(DmxBuffer::with_slots(24)), vec![0; 24]);
     /// ```
     fn from(b: DmxBuffer) -> Self {
-        (b.0 as Box<[u8]>).into()
+        let mut vec = Vec::from(b.data as Box<[u8]>);
+        vec.truncate(b.slots);
+
+        vec
     }
 }
 
@@ -90,7 +121,7 @@ impl TryFrom<&[u8]> for DmxBuffer {
     type Error = TryFromBufferError;
 
     /// Tries to create a DMX buffer by copying from a slice. Succeeds if
-    /// `b.len() == 512`.
+    /// `b` holds between 1 and 512 bytes.
     ///
     /// # Examples
     ///
@@ -100,20 +131,28 @@ impl TryFrom<&[u8]> for DmxBuffer {
     /// let bytes = [0; 1024];
     ///
     /// assert_eq!(DmxBuffer::try_from(&bytes[0..512])?, DmxBuffer::new());
+    /// assert_eq!(DmxBuffer::try_from(&bytes[0..24])?.slots(), 24);
+    /// assert!(DmxBuffer::try_from(&bytes[..]).is_err());
     /// # Ok(()) }
     /// ```
     fn try_from(b: &[u8]) -> Result<Self, Self::Error> {
-        let buffer = b.try_into().map_err(|_| TryFromBufferError(()))?;
+        if !(1..=MAX_SLOTS).contains(&b.len()) {
+            return Err(TryFromBufferError(()));
+        }
+
+        let mut buffer = Self::with_slots(b.len());
+        buffer.copy_from_slice(b);
 
-        Ok(Self(Box::new(buffer)))
+        Ok(buffer)
     }
 }
 
 impl TryFrom<Vec<u8>> for DmxBuffer {
     type Error = TryFromBufferError;
 
-    /// Construct a DMX buffer by transferring ownership of a vector's existing
-    /// heap allocation. Succeeds if `b.len() == 512`.
+    /// Construct a DMX buffer from a vector. Succeeds if `b` holds between 1
+    /// and 512 bytes. A full universe transfers ownership of the vector's
+    /// existing heap allocation, while partial universes are copied.
     ///
     /// # Examples
     ///
@@ -123,26 +162,35 @@ impl TryFrom<Vec<u8>> for DmxBuffer {
     /// let bytes = vec![0; 512];
     ///
     /// assert_eq!(DmxBuffer::try_from(bytes)?, DmxBuffer::new());
+    /// assert_eq!(DmxBuffer::try_from(vec![255; 3])?.slots(), 3);
+    /// assert!(DmxBuffer::try_from(Vec::new()).is_err());
     /// # Ok(()) }
     /// ```
     fn try_from(b: Vec<u8>) -> Result<Self, Self::Error> {
-        let buffer = b.try_into().map_err(|_| TryFromBufferError(()))?;
+        if b.len() != MAX_SLOTS {
+            return Self::try_from(b.as_slice());
+        }
 
-        Ok(Self(buffer))
+        let data = b.try_into().map_err(|_| TryFromBufferError(()))?;
+
+        Ok(Self {
+            data,
+            slots: MAX_SLOTS,
+        })
     }
 }
 
 impl Deref for DmxBuffer {
-    type Target = [u8; 512];
+    type Target = [u8];
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        self.as_slice()
     }
 }
 
 impl DerefMut for DmxBuffer {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        self.as_mut_slice()
     }
 }
 
@@ -177,7 +225,369 @@ impl DmxBuffer {
     /// assert_eq!(*universe, [0; 512]);
     /// ```
     pub fn zero(&mut self) {
-        self.0.fill(0);
+        self.data.fill(0);
+    }
+
+    /// Linearly crossfade from this buffer to `to`, with `position` going
+    /// from 0 (all `self`) to 1 (all `to`). See
+    /// [`Crossfade`](crate::fade::Crossfade) for easing curves, snapping
+    /// channels and 16-bit channels.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ola::DmxBuffer;
+    /// let from = DmxBuffer::new();
+    /// let to = DmxBuffer::from([200; 512]);
+    ///
+    /// let halfway = from.crossfade(&to, 0.5);
+    /// assert_eq!(*halfway, [100; 512]);
+    /// ```
+    pub fn crossfade(&self, to: &DmxBuffer, position: f32) -> DmxBuffer {
+        Crossfade::new().apply(self, to, position)
+    }
+
+    /// Construct an empty (zeroed) DMX buffer holding a partial universe of
+    /// `slots` slots.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `slots` is not between 1 and 512.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ola::DmxBuffer;
+    /// let universe = DmxBuffer::with_slots(24);
+    ///
+    /// assert_eq!(universe.slots(), 24);
+    /// assert_eq!(*universe, [0; 24]);
+    /// ```
+    pub fn with_slots(slots: usize) -> Self {
+        let mut buffer = Self::new();
+        buffer.set_slots(slots);
+
+        buffer
+    }
+
+    /// The number of slots in the universe.
+    pub fn slots(&self) -> usize {
+        self.slots
+    }
+
+    /// Change the number of slots in the universe. Slots added to the end of
+    /// the buffer are zeroed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `slots` is not between 1 and 512.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ola::DmxBuffer;
+    /// let mut universe = DmxBuffer::from([255; 512]);
+    ///
+    /// universe.set_slots(2);
+    /// assert_eq!(*universe, [255, 255]);
+    ///
+    /// universe.set_slots(4);
+    /// assert_eq!(*universe, [255, 255, 0, 0]);
+    /// ```
+    pub fn set_slots(&mut self, slots: usize) {
+        assert!(
+            (1..=MAX_SLOTS).contains(&slots),
+            "a DMX universe must have between 1 and 512 slots, not {slots}"
+        );
+
+        self.data[slots..].fill(0);
+        self.slots = slots;
+    }
+
+    /// View the slots of the universe as a slice.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data[..self.slots]
+    }
+
+    /// View the slots of the universe as a mutable slice.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.data[..self.slots]
+    }
+}
+
+/// Channel addressing. DMX channels are numbered from 1, so channel 1 is
+/// the first slot of the universe (index 0).
+impl DmxBuffer {
+    /// Get the value of a channel, or `None` if the universe has no such
+    /// channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ola::DmxBuffer;
+    /// let mut universe = DmxBuffer::new();
+    /// universe[0] = 255;
+    ///
+    /// assert_eq!(universe.channel(1), Some(255));
+    /// assert_eq!(universe.channel(512), Some(0));
+    /// assert_eq!(universe.channel(0), None);
+    /// assert_eq!(universe.channel(513), None);
+    /// ```
+    pub fn channel(&self, channel: usize) -> Option<u8> {
+        self.get(channel.checked_sub(1)?).copied()
+    }
+
+    /// Set the value of a channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the universe has no such channel. See
+    /// [`try_set_channel`](Self::try_set_channel) for a non-panicking
+    /// version.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ola::DmxBuffer;
+    /// let mut universe = DmxBuffer::new();
+    /// universe.set_channel(1, 255);
+    ///
+    /// assert_eq!(universe[0], 255);
+    /// ```
+    pub fn set_channel(&mut self, channel: usize, value: u8) {
+        if let Err(e) = self.try_set_channel(channel, value) {
+            panic!("{e}");
+        }
+    }
+
+    /// Set the value of a channel, failing if the universe has no such
+    /// channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ola::DmxBuffer;
+    /// let mut universe = DmxBuffer::with_slots(24);
+    ///
+    /// assert!(universe.try_set_channel(24, 255).is_ok());
+    /// assert!(universe.try_set_channel(25, 255).is_err());
+    /// assert!(universe.try_set_channel(0, 255).is_err());
+    /// ```
+    pub fn try_set_channel(&mut self, channel: usize, value: u8) -> Result<(), ChannelError> {
+        let slots = self.slots;
+        let slot = channel
+            .checked_sub(1)
+            .and_then(|index| self.get_mut(index))
+            .ok_or(ChannelError { channel, slots })?;
+        *slot = value;
+
+        Ok(())
+    }
+
+    /// Get a 16-bit value spread over two channels, with the coarse (most
+    /// significant) byte in `channel` and the fine byte in the channel after
+    /// it. Returns `None` if the universe doesn't have both channels.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ola::DmxBuffer;
+    /// let mut universe = DmxBuffer::new();
+    /// universe[0] = 0x12;
+    /// universe[1] = 0x34;
+    ///
+    /// assert_eq!(universe.get_u16(1), Some(0x1234));
+    /// assert_eq!(universe.get_u16(512), None);
+    /// ```
+    pub fn get_u16(&self, channel: usize) -> Option<u16> {
+        let index = channel.checked_sub(1)?;
+        let bytes = self.get(index..index.checked_add(2)?)?;
+
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Set a 16-bit value spread over two channels, with the coarse (most
+    /// significant) byte in `channel` and the fine byte in the channel after
+    /// it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the universe doesn't have both channels. See
+    /// [`try_set_u16`](Self::try_set_u16) for a non-panicking version.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ola::DmxBuffer;
+    /// let mut universe = DmxBuffer::new();
+    /// universe.set_u16(1, 0x1234);
+    ///
+    /// assert_eq!(universe[0], 0x12);
+    /// assert_eq!(universe[1], 0x34);
+    /// ```
+    pub fn set_u16(&mut self, channel: usize, value: u16) {
+        if let Err(e) = self.try_set_u16(channel, value) {
+            panic!("{e}");
+        }
+    }
+
+    /// Set a 16-bit value spread over two channels, failing if the universe
+    /// doesn't have both channels. See [`set_u16`](Self::set_u16).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ola::DmxBuffer;
+    /// let mut universe = DmxBuffer::new();
+    ///
+    /// assert!(universe.try_set_u16(511, 0xffff).is_ok());
+    /// assert!(universe.try_set_u16(512, 0xffff).is_err());
+    /// assert_eq!(universe[511], 0xff);
+    /// ```
+    pub fn try_set_u16(&mut self, channel: usize, value: u16) -> Result<(), ChannelError> {
+        let slots = self.slots;
+        let bytes = channel
+            .checked_sub(1)
+            .and_then(|index| self.get_mut(index..index.checked_add(2)?))
+            .ok_or(ChannelError {
+                // report the fine channel if only that one is missing
+                channel: if (1..=slots).contains(&channel) {
+                    channel + 1
+                } else {
+                    channel
+                },
+                slots,
+            })?;
+        bytes.copy_from_slice(&value.to_be_bytes());
+
+        Ok(())
+    }
+
+    /// Get a range of channels as a slice, or `None` if the range doesn't
+    /// fit in the universe.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ola::DmxBuffer;
+    /// let mut universe = DmxBuffer::new();
+    /// universe[0] = 1;
+    /// universe[1] = 2;
+    /// universe[2] = 3;
+    ///
+    /// assert_eq!(universe.channels(1..=3), Some(&[1, 2, 3][..]));
+    /// assert_eq!(universe.channels(2..4), Some(&[2, 3][..]));
+    /// assert_eq!(universe.channels(510..).map(<[u8]>::len), Some(3));
+    /// assert_eq!(universe.channels(0..=3), None);
+    /// assert_eq!(universe.channels(510..=513), None);
+    /// ```
+    pub fn channels(&self, channels: impl RangeBounds<usize>) -> Option<&[u8]> {
+        let range = slot_range(channels, self.slots)?;
+
+        self.get(range)
+    }
+
+    /// Get a range of channels as a mutable slice, or `None` if the range
+    /// doesn't fit in the universe. See [`channels`](Self::channels).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ola::DmxBuffer;
+    /// let mut universe = DmxBuffer::new();
+    /// if let Some(fixture) = universe.channels_mut(9..=12) {
+    ///     fixture.copy_from_slice(&[255, 0, 128, 0]);
+    /// }
+    ///
+    /// assert_eq!(universe.channel(11), Some(128));
+    /// ```
+    pub fn channels_mut(&mut self, channels: impl RangeBounds<usize>) -> Option<&mut [u8]> {
+        let range = slot_range(channels, self.slots)?;
+
+        self.get_mut(range)
+    }
+}
+
+/// Convert a range of 1-based channels into a range of slot indices.
+pub(crate) fn slot_range(channels: impl RangeBounds<usize>, slots: usize) -> Option<Range<usize>> {
+    let start = match channels.start_bound() {
+        Bound::Included(&channel) => channel.checked_sub(1)?,
+        Bound::Excluded(&channel) => channel,
+        Bound::Unbounded => 0,
+    };
+    let end = match channels.end_bound() {
+        Bound::Included(&channel) => channel,
+        Bound::Excluded(&channel) => channel.checked_sub(1)?,
+        Bound::Unbounded => slots,
+    };
+
+    Some(start..end)
+}
+
+/// Change detection.
+impl DmxBuffer {
+    /// Compare this buffer against a newer version of the universe, yielding
+    /// every channel whose value changed as `(channel, old, new)`. Channels
+    /// are numbered from 1, and slots missing from the shorter buffer count
+    /// as zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ola::DmxBuffer;
+    /// let old = DmxBuffer::new();
+    /// let mut new = old.clone();
+    /// new.set_channel(3, 255);
+    /// new.set_channel(4, 128);
+    /// new.set_channel(10, 1);
+    ///
+    /// let changes: Vec<_> = old.diff(&new).collect();
+    /// assert_eq!(changes, [(3, 0, 255), (4, 0, 128), (10, 0, 1)]);
+    ///
+    /// let ranges: Vec<_> = old.diff(&new).ranges().collect();
+    /// assert_eq!(ranges, [3..=4, 10..=10]);
+    ///
+    /// assert!(old.diff(&old).next().is_none());
+    /// ```
+    pub fn diff<'a>(&'a self, other: &'a DmxBuffer) -> Diff<'a> {
+        Diff {
+            old: self.as_slice(),
+            new: other.as_slice(),
+            slot: 0,
+        }
+    }
+
+    /// A cheap fingerprint of the universe, for telling whether it changed
+    /// without keeping a copy around. Equal buffers always have the same
+    /// fingerprint, and it is stable between runs and versions of this
+    /// crate. Different buffers are very unlikely to share a fingerprint,
+    /// but it isn't guaranteed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ola::DmxBuffer;
+    /// let mut universe = DmxBuffer::new();
+    /// let before = universe.fingerprint();
+    ///
+    /// universe.set_channel(1, 255);
+    /// assert_ne!(universe.fingerprint(), before);
+    ///
+    /// universe.set_channel(1, 0);
+    /// assert_eq!(universe.fingerprint(), before);
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        // 64-bit FNV-1a
+        const OFFSET: u64 = 0xcbf29ce484222325;
+        const PRIME: u64 = 0x100000001b3;
+
+        let length = (self.slots as u16).to_be_bytes();
+        length
+            .iter()
+            .chain(self.as_slice())
+            .fold(OFFSET, |hash, &byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+            })
     }
 }
 
@@ -185,19 +595,19 @@ impl Index<usize> for DmxBuffer {
     type Output = u8;
 
     fn index(&self, index: usize) -> &Self::Output {
-        self.0.index(index)
+        self.as_slice().index(index)
     }
 }
 
 impl IndexMut<usize> for DmxBuffer {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        self.0.index_mut(index)
+        self.as_mut_slice().index_mut(index)
     }
 }
 
 /// The error type returned when a conversion to a DMX buffer fails.
 #[derive(Clone, Debug)]
-pub struct TryFromBufferError(());
+pub struct TryFromBufferError(pub(crate) ());
 
 impl Display for TryFromBufferError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -206,3 +616,206 @@ impl Display for TryFromBufferError {
 }
 
 impl Error for TryFromBufferError {}
+
+/// The error type returned when addressing a channel outside of a DMX
+/// universe.
+#[derive(Clone, Debug)]
+pub struct ChannelError {
+    channel: usize,
+    slots: usize,
+}
+
+impl ChannelError {
+    /// The 1-based channel which was out of range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ola::DmxBuffer;
+    /// let mut universe = DmxBuffer::with_slots(24);
+    ///
+    /// let error = universe.try_set_u16(24, 0xffff).unwrap_err();
+    /// assert_eq!(error.channel(), 25);
+    /// assert_eq!(error.slots(), 24);
+    /// ```
+    pub fn channel(&self) -> usize {
+        self.channel
+    }
+
+    /// The number of slots in the universe the channel was addressed in.
+    pub fn slots(&self) -> usize {
+        self.slots
+    }
+}
+
+impl Display for ChannelError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "channel {} is outside of a universe with {} slots",
+            self.channel, self.slots
+        )
+    }
+}
+
+impl Error for ChannelError {}
+
+/// An iterator over the channels which differ between two DMX buffers, as
+/// `(channel, old, new)`. See [`DmxBuffer::diff`].
+#[derive(Clone, Debug)]
+pub struct Diff<'a> {
+    old: &'a [u8],
+    new: &'a [u8],
+    slot: usize,
+}
+
+impl<'a> Diff<'a> {
+    /// Group the changed channels into inclusive ranges of consecutive
+    /// channels.
+    pub fn ranges(self) -> ChangedRanges<'a> {
+        ChangedRanges { diff: self }
+    }
+
+    fn len(&self) -> usize {
+        self.old.len().max(self.new.len())
+    }
+
+    fn changed(&self, slot: usize) -> Option<(u8, u8)> {
+        let old = self.old.get(slot).copied().unwrap_or(0);
+        let new = self.new.get(slot).copied().unwrap_or(0);
+
+        (old != new).then_some((old, new))
+    }
+}
+
+impl Iterator for Diff<'_> {
+    type Item = (usize, u8, u8);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.slot < self.len() {
+            let slot = self.slot;
+            self.slot += 1;
+
+            if let Some((old, new)) = self.changed(slot) {
+                return Some((slot + 1, old, new));
+            }
+        }
+
+        None
+    }
+}
+
+impl FusedIterator for Diff<'_> {}
+
+/// An iterator over the ranges of consecutive channels which differ between
+/// two DMX buffers. See [`Diff::ranges`].
+#[derive(Clone, Debug)]
+pub struct ChangedRanges<'a> {
+    diff: Diff<'a>,
+}
+
+impl Iterator for ChangedRanges<'_> {
+    type Item = RangeInclusive<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start, _, _) = self.diff.next()?;
+
+        // the diff has moved past the first changed channel, so extend the
+        // range for as long as the following channels changed too
+        while self.diff.slot < self.diff.len() && self.diff.changed(self.diff.slot).is_some() {
+            self.diff.slot += 1;
+        }
+
+        Some(start..=self.diff.slot)
+    }
+}
+
+impl FusedIterator for ChangedRanges<'_> {}
+
+/// Serialized as the bytes of the universe, or as a base64 string in
+/// human-readable formats such as JSON. See [`channel_map`](crate::channel_map)
+/// for a representation which is easier to read and edit by hand.
+///
+/// # Examples
+///
+/// ```
+/// # use ola::DmxBuffer;
+/// let universe = DmxBuffer::try_from(vec![255, 0, 128]).unwrap();
+///
+/// let json = serde_json::to_string(&universe)?;
+/// assert_eq!(json, r#""/wCA""#);
+/// assert_eq!(serde_json::from_str::<DmxBuffer>(&json)?, universe);
+///
+/// // arrays of values are accepted too
+/// assert_eq!(serde_json::from_str::<DmxBuffer>("[255, 0, 128]")?, universe);
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+#[cfg(feature = "serde")]
+impl serde::Serialize for DmxBuffer {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use base64::Engine;
+
+        if serializer.is_human_readable() {
+            let encoded = base64::engine::general_purpose::STANDARD.encode(self.as_slice());
+            serializer.serialize_str(&encoded)
+        } else {
+            serializer.serialize_bytes(self.as_slice())
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for DmxBuffer {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(BufferVisitor)
+        } else {
+            deserializer.deserialize_bytes(BufferVisitor)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+struct BufferVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> serde::de::Visitor<'de> for BufferVisitor {
+    type Value = DmxBuffer;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "between 1 and 512 bytes of DMX data")
+    }
+
+    fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        DmxBuffer::try_from(v).map_err(|_| E::invalid_length(v.len(), &self))
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        use base64::Engine;
+
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(v)
+            .map_err(E::custom)?;
+        self.visit_bytes(&bytes)
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        use serde::de::Error;
+
+        let mut buffer = DmxBuffer::new();
+        let mut slots = 0;
+        while let Some(value) = seq.next_element()? {
+            if slots == MAX_SLOTS {
+                return Err(A::Error::invalid_length(slots + 1, &self));
+            }
+            buffer.data[slots] = value;
+            slots += 1;
+        }
+        if slots == 0 {
+            return Err(A::Error::invalid_length(0, &self));
+        }
+        buffer.slots = slots;
+
+        Ok(buffer)
+    }
+}
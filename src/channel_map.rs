@@ -0,0 +1,85 @@
+//! Serialize a [`DmxBuffer`] as a map of channels.
+//!
+//! By default, DMX buffers are serialized as their raw bytes (or base64 in
+//! human-readable formats). Using this module with `#[serde(with = "...")]`
+//! instead serializes them as the number of slots and a map from channel
+//! number to value, leaving out channels set to zero. This is much easier to
+//! read and edit by hand, for example in scene files.
+//!
+//! # Examples
+//!
+//! ```
+//! # use ola::DmxBuffer;
+//! # use serde::{Deserialize, Serialize};
+//! #[derive(Serialize, Deserialize)]
+//! struct Scene {
+//!     name: String,
+//!     #[serde(with = "ola::channel_map")]
+//!     universe: DmxBuffer,
+//! }
+//!
+//! let mut universe = DmxBuffer::with_slots(24);
+//! universe.set_channel(1, 255);
+//! universe.set_channel(10, 128);
+//! let scene = Scene { name: "warm".into(), universe };
+//!
+//! let json = serde_json::to_string(&scene)?;
+//! assert_eq!(
+//!     json,
+//!     r#"{"name":"warm","universe":{"slots":24,"channels":{"1":255,"10":128}}}"#
+//! );
+//!
+//! let scene: Scene = serde_json::from_str(&json)?;
+//! assert_eq!(scene.universe.channel(10), Some(128));
+//! # Ok::<(), serde_json::Error>(())
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::DmxBuffer;
+
+#[derive(Serialize, Deserialize)]
+struct ChannelMap {
+    slots: usize,
+    channels: BTreeMap<usize, u8>,
+}
+
+/// Serialize a DMX buffer as a map of channels.
+pub fn serialize<S: Serializer>(buffer: &DmxBuffer, serializer: S) -> Result<S::Ok, S::Error> {
+    let channels = buffer
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| **value != 0)
+        .map(|(slot, value)| (slot + 1, *value))
+        .collect();
+
+    ChannelMap {
+        slots: buffer.slots(),
+        channels,
+    }
+    .serialize(serializer)
+}
+
+/// Deserialize a DMX buffer from a map of channels. Channels which aren't
+/// listed are set to zero.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DmxBuffer, D::Error> {
+    let map = ChannelMap::deserialize(deserializer)?;
+    if !(1..=512).contains(&map.slots) {
+        return Err(D::Error::custom(format!(
+            "a DMX universe must have between 1 and 512 slots, not {}",
+            map.slots
+        )));
+    }
+
+    let mut buffer = DmxBuffer::with_slots(map.slots);
+    for (channel, value) in map.channels {
+        buffer
+            .try_set_channel(channel, value)
+            .map_err(D::Error::custom)?;
+    }
+
+    Ok(buffer)
+}
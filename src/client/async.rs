@@ -1,25 +1,299 @@
-use super::{CallError, CallErrorKind};
-use crate::ola::proto::{
-    rpc::{RpcMessage, Type},
-    Ack, DmxData, OlaClientServiceCall, OlaServerServiceCall, RegisterAction, RegisterDmxRequest,
-};
-use crate::ola::{decode_header, RpcContext};
+use super::core::{with_deadline, Core, READ_CHUNK_SIZE};
+use super::{dmx_update, CallError, CallErrorKind};
+use crate::ola::proto::{Ack, OlaServerServiceCall};
+use crate::ola::{Endianness, MessageDecodeError, MessageDecodeErrorKind};
 use crate::DmxBuffer;
 
-use bytes::BytesMut;
+use std::collections::{HashMap, VecDeque};
+use std::future::{poll_fn, Future};
+use std::marker::PhantomData;
+use std::pin::{pin, Pin};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
 use prost::Message;
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::{oneshot, watch, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Sleep};
+
+/// Channel a call's response (or, for streamed calls, an empty buffer once
+/// the call has been written out) is delivered on.
+type Reply = oneshot::Sender<Result<Vec<u8>, CallError>>;
+
+/// An asynchronous client for OLA.
+///
+/// The connection is driven by a background task, spawned on the current
+/// Tokio runtime when the client is created. The client itself is a cheap
+/// handle to it which can be cloned and shared between tasks, allowing calls
+/// to be made concurrently over the same connection. Responses are routed
+/// back to the right caller by their sequence number.
+///
+/// The connection is closed once every handle has been dropped, or by
+/// calling [`close`](Self::close). Calls still waiting for a response then
+/// fail with [`CallErrorKind::Disconnected`].
+#[derive(Clone, Debug)]
+pub struct ClientAsync {
+    inner: Arc<Inner>,
+}
+
+/// Owner of the background task, aborting it once the last handle is gone.
+/// Anything still waiting on the connection then fails, as no one is left to
+/// drive it.
+#[derive(Debug)]
+struct Inner {
+    shared: Arc<Shared>,
+    task: Mutex<Option<JoinHandle<Result<(), CallError>>>>,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        if let Some(task) = lock(&self.task).take() {
+            task.abort();
+            self.shared.finish(CallError {
+                kind: CallErrorKind::Disconnected,
+            });
+        }
+    }
+}
+
+/// State shared between the client handles and the background task.
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    /// Notified when an update is queued or the connection ends.
+    updated: Notify,
+}
+
+impl Shared {
+    /// Fail every outstanding call after the connection ended.
+    fn finish(&self, error: CallError) {
+        lock(&self.state).finish(error);
+        self.updated.notify_waiters();
+    }
+}
 
 #[derive(Debug)]
-pub struct ClientAsync<S: AsyncRead + AsyncWrite + Unpin> {
-    stream: S,
-    ctx: RpcContext,
-    buf: BytesMut,
+struct State {
+    core: Core,
+    /// Callers waiting for the response to a call, by sequence number.
+    waiters: HashMap<u32, Reply>,
+    /// Streamed calls waiting for the connection to have written the given
+    /// total number of bytes.
+    flushes: VecDeque<(u64, Reply)>,
+    /// How far the connection got writing. Streaming DMX waits on this
+    /// rather than `flushes`, so it doesn't allocate for every frame.
+    progress: watch::Sender<Progress>,
+    write_timeout: Option<Duration>,
+    call_timeout: Option<Duration>,
+    closing: bool,
+    /// Waker of the background task, to be woken when there is more to send.
+    waker: Option<Waker>,
+}
+
+/// How far the background task got writing to the connection.
+#[derive(Clone, Copy, Debug, Default)]
+struct Progress {
+    /// Total number of bytes written to the stream.
+    written: u64,
+    /// Whether the connection ended, so nothing more will be written.
+    ended: bool,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // the state is kept consistent between panics, so poisoning is ignored
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl State {
+    /// The total number of bytes written once everything queued so far has
+    /// been.
+    fn queued(&self) -> u64 {
+        self.progress.borrow().written + self.core.session.transmit().len() as u64
+    }
+
+    /// Get notified once everything queued so far has been written.
+    fn flushed(&mut self) -> oneshot::Receiver<Result<Vec<u8>, CallError>> {
+        let (reply, response) = oneshot::channel();
+        self.flushes.push_back((self.queued(), reply));
+        self.wake();
+
+        response
+    }
+
+    /// Watch for everything queued so far to be written. Returns the total
+    /// number of bytes written by then, to be passed to [`written`].
+    fn watch_written(&mut self) -> (watch::Receiver<Progress>, u64) {
+        let target = self.queued();
+        self.wake();
+
+        (self.progress.subscribe(), target)
+    }
+
+    /// Wake the background task so it sends anything newly queued.
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// Hand out responses decoded by the session, queueing any updates.
+    fn dispatch(&mut self) -> Result<(), CallError> {
+        let waiters = &mut self.waiters;
+        self.core.dispatch(|id, result| {
+            if let Some(reply) = waiters.remove(&id) {
+                let _ = reply.send(result);
+            }
+        })
+    }
+
+    /// Record that `count` more bytes were written, notifying anyone waiting
+    /// for them.
+    fn advance(&mut self, count: usize) {
+        self.core.session.advance_transmit(count);
+        self.progress
+            .send_modify(|progress| progress.written += count as u64);
+
+        let written = self.progress.borrow().written;
+        while let Some((target, _)) = self.flushes.front() {
+            if *target > written {
+                break;
+            }
+            if let Some((_, reply)) = self.flushes.pop_front() {
+                let _ = reply.send(Ok(Vec::new()));
+            }
+        }
+    }
+
+    /// Fail every outstanding call after the connection ended.
+    fn finish(&mut self, error: CallError) {
+        for (_, reply) in self.waiters.drain() {
+            let _ = reply.send(Err(error.duplicate()));
+        }
+        for (_, reply) in self.flushes.drain(..) {
+            let _ = reply.send(Err(error.duplicate()));
+        }
+        self.core.set_failed(error);
+        self.progress.send_modify(|progress| progress.ended = true);
+    }
+}
+
+/// Drive the connection until it fails or the client is closed.
+async fn run<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    shared: Arc<Shared>,
+) -> Result<(), CallError> {
+    let mut write_deadline: Option<Pin<Box<Sleep>>> = None;
+    let result = poll_fn(|cx| {
+        let mut state = lock(&shared.state);
+        state.waker = Some(cx.waker().clone());
+
+        loop {
+            let mut chunk = [0; READ_CHUNK_SIZE];
+            let mut buf = ReadBuf::new(&mut chunk);
+            match Pin::new(&mut stream).poll_read(cx, &mut buf) {
+                Poll::Ready(Ok(())) if buf.filled().is_empty() => {
+                    return Poll::Ready(Err(CallError {
+                        kind: CallErrorKind::Disconnected,
+                    }))
+                }
+                Poll::Ready(Ok(())) => {
+                    // hand out whatever was decoded before failing
+                    let received = state.core.receive(buf.filled());
+                    state.dispatch()?;
+                    // an undecodable message is skipped, only ending the
+                    // connection if its framing was lost
+                    if let Err(e) = received {
+                        if state.core.check().is_err() {
+                            return Poll::Ready(Err(e));
+                        }
+                    }
+                    if state.core.has_updates() {
+                        shared.updated.notify_waiters();
+                    }
+                }
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(CallError::read(e))),
+                Poll::Pending => break,
+            }
+        }
+
+        while !state.core.session.transmit().is_empty() {
+            match Pin::new(&mut stream).poll_write(cx, state.core.session.transmit()) {
+                Poll::Ready(Ok(0)) => {
+                    return Poll::Ready(Err(CallError {
+                        kind: CallErrorKind::Disconnected,
+                    }))
+                }
+                Poll::Ready(Ok(count)) => {
+                    write_deadline = None;
+                    state.advance(count);
+                }
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(CallError::write(e))),
+                Poll::Pending => {
+                    if let Some(duration) = state.write_timeout {
+                        let deadline =
+                            write_deadline.get_or_insert_with(|| Box::pin(sleep(duration)));
+                        if deadline.as_mut().poll(cx).is_ready() {
+                            return Poll::Ready(Err(CallError {
+                                kind: CallErrorKind::Timeout,
+                            }));
+                        }
+                    }
+                    return Poll::Pending;
+                }
+            }
+        }
+
+        if let Poll::Ready(Err(e)) = Pin::new(&mut stream).poll_flush(cx) {
+            return Poll::Ready(Err(CallError::write(e)));
+        }
+        if state.closing {
+            return Pin::new(&mut stream)
+                .poll_shutdown(cx)
+                .map_err(CallError::write);
+        }
+
+        Poll::Pending
+    })
+    .await;
+
+    let error = match &result {
+        Ok(()) => CallError {
+            kind: CallErrorKind::Disconnected,
+        },
+        Err(e) => e.duplicate(),
+    };
+    shared.finish(error);
+
+    result
 }
 
-impl<S: AsyncRead + AsyncWrite + Unpin> ClientAsync<S> {
+/// Wait until the connection has written `target` bytes in total, as set up
+/// by [`State::watch_written`].
+async fn written(
+    mut progress: watch::Receiver<Progress>,
+    target: u64,
+    shared: &Shared,
+) -> Result<(), CallError> {
+    let reached = progress
+        .wait_for(|progress| progress.written >= target || progress.ended)
+        .await
+        .is_ok_and(|progress| progress.written >= target);
+    if reached {
+        return Ok(());
+    }
+
+    // the connection ended first
+    lock(&shared.state).core.check()?;
+    Err(CallError {
+        kind: CallErrorKind::Disconnected,
+    })
+}
+
+impl ClientAsync {
     pub async fn send_dmx_streaming(
-        &mut self,
+        &self,
         universe: u32,
         data: &DmxBuffer,
     ) -> Result<(), CallError> {
@@ -27,104 +301,340 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ClientAsync<S> {
             .await
     }
 
+    /// Stream a DMX buffer to an OLA universe with a priority value. This
+    /// completes once the buffer has been written to the connection.
     pub async fn send_dmx_streaming_with_priority(
-        &mut self,
+        &self,
         universe: u32,
         data: &DmxBuffer,
         priority: u8,
     ) -> Result<(), CallError> {
-        let request = OlaServerServiceCall::StreamDmxData(DmxData {
-            universe: universe as i32,
-            data: data.to_vec(),
-            priority: Some(priority as i32),
-        });
+        let (progress, target, deadline) = {
+            let mut state = self.state()?;
+            state.core.send_dmx(universe, data, priority)?;
+            let (progress, target) = state.watch_written();
 
-        let mut buf = BytesMut::new();
-        self.ctx.encode(request, &mut buf).map_err(|e| CallError {
-            kind: CallErrorKind::Encode(e),
-        })?;
-        self.stream.write_all(&buf).await.map_err(|e| CallError {
-            kind: CallErrorKind::Write(e),
-        })?;
-        Ok(())
+            (progress, target, state.call_timeout)
+        };
+
+        let written = written(progress, target, &self.inner.shared);
+        with_deadline(deadline, sleep, written).await
     }
 
-    pub async fn register_universe(&mut self, universe: u32) -> Result<(), CallError> {
-        let request = OlaServerServiceCall::RegisterForDmx(RegisterDmxRequest {
-            universe: universe as i32,
-            action: RegisterAction::Register as i32,
-        });
+    /// Stream DMX buffers to several OLA universes at once, as
+    /// `(universe, buffer, priority)`. All buffers are written to the
+    /// connection together, so OLA receives them as one coherent frame.
+    pub async fn send_dmx_many(&self, frames: &[(u32, &DmxBuffer, u8)]) -> Result<(), CallError> {
+        let (progress, target, deadline) = {
+            let mut state = self.state()?;
+            state.core.send_dmx_many(frames)?;
+            let (progress, target) = state.watch_written();
 
-        let mut buf = BytesMut::new();
-        self.ctx.encode(request, &mut buf).map_err(|e| CallError {
-            kind: CallErrorKind::Encode(e),
-        })?;
-        self.stream.write_all(&buf).await.map_err(|e| CallError {
-            kind: CallErrorKind::Write(e),
-        })?;
-        Ok(())
+            (progress, target, state.call_timeout)
+        };
+
+        let written = written(progress, target, &self.inner.shared);
+        with_deadline(deadline, sleep, written).await
     }
 
-    pub async fn recv(&mut self) -> Result<(i32, DmxBuffer), CallError> {
-        loop {
-            let _ = self.stream.read_buf(&mut self.buf).await;
+    pub async fn register_universe(&self, universe: u32) -> Result<(), CallError> {
+        self.call_with(|core| core.register_universe(universe))?
+            .await
+            .map(|_: Ack| ())
+    }
 
-            if self.buf.len() < 4 {
-                // must load more to read header
-                continue;
-            }
+    /// Make an RPC call to OLA, decoding its response as `R`.
+    ///
+    /// The call is queued right away, and completes when OLA answers it (or
+    /// the call deadline passes). Dropping the returned [`PendingCall`]
+    /// before then cancels it. Streamed calls, which OLA never answers,
+    /// complete with `R::default()` once they have been written to the
+    /// connection.
+    pub fn call<R: Message + Default>(
+        &self,
+        request: OlaServerServiceCall,
+    ) -> Result<PendingCall<R>, CallError> {
+        self.call_with(|core| core.session.call(request))
+    }
+
+    /// Make an RPC call queued by `queue`, which returns its sequence number.
+    fn call_with<R: Message + Default>(
+        &self,
+        queue: impl FnOnce(&mut Core) -> Result<u32, CallError>,
+    ) -> Result<PendingCall<R>, CallError> {
+        let mut state = self.state()?;
+        let id = queue(&mut state.core)?;
+        let response = if state.core.session.is_pending(id) {
+            let (reply, response) = oneshot::channel();
+            state.waiters.insert(id, reply);
+            state.wake();
+
+            response
+        } else {
+            state.flushed()
+        };
+
+        Ok(PendingCall {
+            shared: self.inner.shared.clone(),
+            id,
+            response,
+            deadline: state.call_timeout.map(|duration| Box::pin(sleep(duration))),
+            decode: PhantomData,
+        })
+    }
+
+    /// Close the connection with OLA.
+    ///
+    /// All universes registered with [`register_universe`] are unregistered
+    /// and any pending writes are flushed before the stream is shut down. If
+    /// `blackout` is set, every universe this client has streamed to is sent
+    /// a zeroed buffer first (at the priority last used for it).
+    ///
+    /// This closes the connection for every clone of the client. Calls still
+    /// waiting for a response fail with [`CallErrorKind::Disconnected`]. If
+    /// the call timeout passes before everything was written, the connection
+    /// is dropped as-is and this fails with [`CallErrorKind::Timeout`].
+    ///
+    /// [`register_universe`]: Self::register_universe
+    pub async fn close(self, blackout: bool) -> Result<(), CallError> {
+        let deadline = {
+            let mut state = self.state()?;
+            state.core.close(blackout)?;
+
+            state.closing = true;
+            state.wake();
 
-            let mut header = [0; 4];
-            header.copy_from_slice(&self.buf[0..4]);
-            let (_version, size) = decode_header(header);
-            if self.buf.len() < 4 + size {
-                // must load more to read entire message
-                continue;
+            state.call_timeout
+        };
+
+        let task = lock(&self.inner.task).take();
+        match task {
+            Some(mut task) => {
+                let result = with_deadline(deadline, sleep, async {
+                    (&mut task).await.unwrap_or(Err(CallError {
+                        kind: CallErrorKind::Disconnected,
+                    }))
+                })
+                .await;
+
+                if !task.is_finished() {
+                    // the deadline passed first, so give up on the connection
+                    task.abort();
+                    self.inner.shared.finish(CallError {
+                        kind: CallErrorKind::Disconnected,
+                    });
+                }
+
+                result
             }
+            None => Ok(()),
+        }
+    }
+
+    /// Wait for the next update to a registered universe.
+    ///
+    /// Updates are queued as they arrive, whether or not anyone is waiting
+    /// for them. Only the latest update to each universe is kept, so falling
+    /// behind skips stale frames rather than queueing them without limit.
+    /// When the client is shared, each update is handed to only one of the
+    /// callers.
+    pub async fn recv(&self) -> Result<(i32, DmxBuffer), CallError> {
+        loop {
+            // register for notifications before checking, so none are missed
+            let mut updated = pin!(self.inner.shared.updated.notified());
+            updated.as_mut().enable();
 
-            let frame = self.buf.split_to(4 + size);
-            let (id, call) = RpcContext::decode(&frame[4..]).map_err(|e| CallError {
-                kind: CallErrorKind::Decode(e),
-            })?;
-
-            match call {
-                OlaClientServiceCall::UpdateDmxData(data) => {
-                    let mut buf = BytesMut::new();
-                    let message = RpcMessage {
-                        r#type: Type::Response as i32,
-                        id: Some(id),
-                        name: Some("Ack".to_string()),
-                        buffer: Some(Ack {}.encode_to_vec()),
-                    };
-                    self.ctx
-                        .encode_message(message, &mut buf)
-                        .map_err(|e| CallError {
-                            kind: CallErrorKind::Encode(e),
-                        })?;
-                    self.stream.write_all(&buf).await.map_err(|e| CallError {
-                        kind: CallErrorKind::Write(e),
-                    })?;
-
-                    return Ok((
-                        data.universe,
-                        data.data.try_into().map_err(|e| CallError {
-                            kind: CallErrorKind::InvalidBuffer(e),
-                        })?,
-                    ));
+            {
+                let mut state = lock(&self.inner.shared.state);
+                if let Some(data) = state.core.next_update() {
+                    return dmx_update(data);
                 }
+                state.core.check()?;
             }
+
+            updated.await;
         }
     }
 
+    /// Whether the connection has ended, failing every call.
+    pub(crate) fn is_failed(&self) -> bool {
+        lock(&self.inner.shared.state).core.check().is_err()
+    }
+
+    /// Lock the shared state, failing if the connection has ended.
+    fn state(&self) -> Result<MutexGuard<'_, State>, CallError> {
+        let state = lock(&self.inner.shared.state);
+        state.core.check()?;
+
+        Ok(state)
+    }
+
+    /// Set how long writing a message may block before the connection fails
+    /// with [`CallErrorKind::Timeout`]. `None` (the default) disables the
+    /// timeout.
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) {
+        lock(&self.inner.shared.state).write_timeout = timeout;
+    }
+
+    /// Set the deadline for completing an entire RPC call, after which it
+    /// fails with [`CallErrorKind::Timeout`]. `None` (the default) disables
+    /// the deadline. This does not apply to [`recv`](Self::recv), which waits
+    /// for updates for as long as it takes.
+    pub fn set_call_timeout(&self, timeout: Option<Duration>) {
+        lock(&self.inner.shared.state).call_timeout = timeout;
+    }
+
+    /// Set the largest message accepted from OLA, in bytes. See
+    /// [`Config::max_frame_size`](crate::config::Config::max_frame_size).
+    pub fn set_max_frame_size(&self, size: usize) {
+        lock(&self.inner.shared.state).core.set_max_frame_size(size);
+    }
+
+    /// Set the byte order of message headers. See
+    /// [`Config::endianness`](crate::config::Config::endianness).
+    pub fn set_endianness(&self, endianness: Endianness) {
+        lock(&self.inner.shared.state)
+            .core
+            .set_endianness(endianness);
+    }
+
     /// Construct a new streaming async client from an async stream. The
     /// client is initialized with a fresh context. This usually shouldn't be
     /// called directly as `ClientConfig::connect_async()` will set up a
     /// stream for you before internally calling this.
-    pub fn from_stream(stream: S) -> Self {
+    ///
+    /// Any transport implementing Tokio's `AsyncRead` and `AsyncWrite` can be
+    /// used, such as a `UnixStream`, an in-memory `DuplexStream` or a
+    /// TLS-wrapped stream.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime, as the connection is
+    /// driven by a task spawned on it.
+    pub fn from_stream<S>(stream: S) -> Self
+    where
+        S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                core: Core::new(),
+                waiters: HashMap::new(),
+                flushes: VecDeque::new(),
+                progress: watch::Sender::new(Progress::default()),
+                write_timeout: None,
+                call_timeout: None,
+                closing: false,
+                waker: None,
+            }),
+            updated: Notify::new(),
+        });
+        let task = tokio::spawn(run(stream, shared.clone()));
+
         Self {
-            stream,
-            ctx: RpcContext::new(),
-            buf: BytesMut::new(),
+            inner: Arc::new(Inner {
+                shared,
+                task: Mutex::new(Some(task)),
+            }),
+        }
+    }
+}
+
+/// An RPC call to OLA which is waiting for its response, returned by
+/// [`ClientAsync::call`].
+///
+/// Dropping a pending call cancels it, telling OLA the call no longer needs
+/// to be answered. Use [`cancel`](Self::cancel) to also wait for the
+/// cancellation to be sent.
+///
+/// A pending call doesn't keep the connection open: once every handle to the
+/// client is gone, it fails with [`CallErrorKind::Disconnected`].
+///
+/// ```
+/// # use ola::client::{CallErrorKind, ClientAsync};
+/// # use ola::ola::proto::{OlaServerServiceCall, OptionalUniverseRequest, UniverseInfoReply};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let (stream, _olad) = tokio::io::duplex(4096);
+/// let client = ClientAsync::from_stream(stream);
+/// let request = OptionalUniverseRequest { universe: None };
+/// let call = client.call::<UniverseInfoReply>(OlaServerServiceCall::GetUniverseInfo(request))?;
+///
+/// drop(client);
+/// let error = call.await.unwrap_err();
+/// assert!(matches!(error.kind, CallErrorKind::Disconnected));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+#[must_use = "calls are cancelled when dropped"]
+pub struct PendingCall<R> {
+    shared: Arc<Shared>,
+    id: u32,
+    response: oneshot::Receiver<Result<Vec<u8>, CallError>>,
+    deadline: Option<Pin<Box<Sleep>>>,
+    decode: PhantomData<fn() -> R>,
+}
+
+impl<R> PendingCall<R> {
+    /// The sequence number of the call.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Cancel the call, telling OLA it no longer needs to be answered.
+    pub async fn cancel(self) -> Result<(), CallError> {
+        let (progress, target, deadline) = {
+            let mut state = lock(&self.shared.state);
+            state.core.check()?;
+            if state.core.session.cancel(self.id)? {
+                state.waiters.remove(&self.id);
+            }
+            let (progress, target) = state.watch_written();
+
+            (progress, target, state.call_timeout)
+        };
+
+        with_deadline(deadline, sleep, written(progress, target, &self.shared)).await
+    }
+}
+
+impl<R: Message + Default> Future for PendingCall<R> {
+    type Output = Result<R, CallError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if let Poll::Ready(result) = Pin::new(&mut this.response).poll(cx) {
+            let buffer = result.unwrap_or(Err(CallError {
+                kind: CallErrorKind::Disconnected,
+            }))?;
+
+            return Poll::Ready(R::decode(&buffer[..]).map_err(|e| CallError {
+                kind: CallErrorKind::Decode(MessageDecodeError {
+                    kind: MessageDecodeErrorKind::Invalid(e),
+                }),
+            }));
+        }
+
+        if let Some(deadline) = &mut this.deadline {
+            if deadline.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Err(CallError {
+                    kind: CallErrorKind::Timeout,
+                }));
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+impl<R> Drop for PendingCall<R> {
+    fn drop(&mut self) {
+        // only sends the cancellation if the call is still unanswered
+        let mut state = lock(&self.shared.state);
+        if state.core.check().is_ok() && matches!(state.core.session.cancel(self.id), Ok(true)) {
+            state.waiters.remove(&self.id);
+            state.wake();
         }
     }
 }
@@ -0,0 +1,241 @@
+use super::{CallError, CallErrorKind};
+use crate::ola::{proto::DmxData, Endianness};
+use crate::session::{Event, Session};
+use crate::DmxBuffer;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use std::future::{poll_fn, Future};
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use std::pin::pin;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use std::task::Poll;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use std::time::Duration;
+
+/// How much is read from the connection at once.
+pub(crate) const READ_CHUNK_SIZE: usize = 4096;
+
+/// Run a future, giving up and returning `None` if `timer` finishes first.
+/// Taking the timer as a future keeps this independent of the runtime.
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub(crate) async fn timeout<F: Future>(timer: impl Future, future: F) -> Option<F::Output> {
+    let mut future = pin!(future);
+    let mut timer = pin!(timer);
+
+    poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            Poll::Ready(Some(output))
+        } else if timer.as_mut().poll(cx).is_ready() {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+/// Run an RPC call, failing with a timeout error if it doesn't finish within
+/// `deadline`. The deadline is timed with the future returned by `sleep`.
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub(crate) async fn with_deadline<T, F, S>(
+    deadline: Option<Duration>,
+    sleep: impl FnOnce(Duration) -> S,
+    call: F,
+) -> Result<T, CallError>
+where
+    F: Future<Output = Result<T, CallError>>,
+    S: Future,
+{
+    match deadline {
+        Some(deadline) => timeout(sleep(deadline), call)
+            .await
+            .unwrap_or(Err(CallError {
+                kind: CallErrorKind::Timeout,
+            })),
+        None => call.await,
+    }
+}
+
+/// State of a client connection, shared by every client regardless of how it
+/// performs I/O.
+///
+/// On top of the protocol state kept by the [`Session`], this remembers what
+/// the client streamed to and registered for (so it can clean up when
+/// closing), queues DMX updates until they are received, and keeps track of
+/// whether the connection is still usable.
+///
+/// Only the latest update to each universe is queued, which bounds the queue
+/// by the number of registered universes however long updates go unreceived.
+#[derive(Debug)]
+pub(crate) struct Core {
+    pub(crate) session: Session,
+    updates: VecDeque<DmxData>,
+    registered: HashSet<u32>,
+    streamed: HashMap<u32, u8>,
+    /// Why the connection became unusable, once it has.
+    error: Option<CallError>,
+}
+
+impl Core {
+    pub(crate) fn new() -> Self {
+        Self {
+            session: Session::new(),
+            updates: VecDeque::new(),
+            registered: HashSet::new(),
+            streamed: HashMap::new(),
+            error: None,
+        }
+    }
+
+    /// Fail if the connection has become unusable.
+    pub(crate) fn check(&self) -> Result<(), CallError> {
+        match &self.error {
+            Some(e) => Err(e.duplicate()),
+            None => Ok(()),
+        }
+    }
+
+    /// Mark the connection as unusable, failing every later call with
+    /// `error`.
+    pub(crate) fn set_failed(&mut self, error: CallError) {
+        if self.error.is_none() {
+            self.error = Some(error);
+        }
+    }
+
+    /// Pass on an error from the connection, marking the connection as
+    /// unusable if the error left it that way.
+    ///
+    /// Only errors from reading or writing the stream are passed here, so a
+    /// timeout means a write timed out, possibly part way through a message.
+    /// Call deadlines are returned to the caller without going through this.
+    pub(crate) fn fail(&mut self, error: CallError) -> CallError {
+        if error.is_disconnect() || matches!(error.kind, CallErrorKind::Timeout) {
+            self.set_failed(error.duplicate());
+        }
+
+        error
+    }
+
+    /// Queue a DMX buffer to be streamed to a universe.
+    pub(crate) fn send_dmx(
+        &mut self,
+        universe: u32,
+        data: &DmxBuffer,
+        priority: u8,
+    ) -> Result<(), CallError> {
+        self.check()?;
+        self.session.send_dmx(universe, data, priority)?;
+        self.streamed.insert(universe, priority);
+
+        Ok(())
+    }
+
+    /// Queue DMX buffers to be streamed to several universes.
+    pub(crate) fn send_dmx_many(
+        &mut self,
+        frames: &[(u32, &DmxBuffer, u8)],
+    ) -> Result<(), CallError> {
+        for &(universe, data, priority) in frames {
+            self.send_dmx(universe, data, priority)?;
+        }
+
+        Ok(())
+    }
+
+    /// Queue a call registering for updates to a universe, returning its
+    /// sequence number.
+    pub(crate) fn register_universe(&mut self, universe: u32) -> Result<u32, CallError> {
+        self.check()?;
+        let id = self.session.register_universe(universe)?;
+        self.registered.insert(universe);
+
+        Ok(id)
+    }
+
+    /// Queue everything that needs to be sent before closing the connection:
+    /// unregistering from every universe and, if `blackout` is set, a zeroed
+    /// buffer for every universe streamed to.
+    pub(crate) fn close(&mut self, blackout: bool) -> Result<(), CallError> {
+        self.check()?;
+        if blackout {
+            let data = DmxBuffer::new();
+            for (universe, priority) in std::mem::take(&mut self.streamed) {
+                self.session.send_dmx(universe, &data, priority)?;
+            }
+        }
+
+        for universe in std::mem::take(&mut self.registered) {
+            self.session.unregister_universe(universe)?;
+        }
+
+        Ok(())
+    }
+
+    /// Feed bytes read from the connection into the session.
+    pub(crate) fn receive(&mut self, data: &[u8]) -> Result<(), CallError> {
+        self.check()?;
+        self.session.receive(data).map_err(|e| self.fail(e))
+    }
+
+    /// Handle the events decoded by the session. DMX updates are queued for
+    /// [`next_update`](Self::next_update), while the outcome of every call
+    /// is passed to `reply` along with its sequence number.
+    pub(crate) fn dispatch(
+        &mut self,
+        mut reply: impl FnMut(u32, Result<Vec<u8>, CallError>),
+    ) -> Result<(), CallError> {
+        while let Some(event) = self.session.poll_event() {
+            match event {
+                Event::Response { id, buffer } => reply(id, Ok(buffer)),
+                Event::Rejected { id, reason } => reply(
+                    id,
+                    Err(CallError {
+                        kind: CallErrorKind::Rejected(reason),
+                    }),
+                ),
+                Event::Dmx(data) => self.queue_update(data),
+                Event::Disconnected => {
+                    return Err(self.fail(CallError {
+                        kind: CallErrorKind::Disconnected,
+                    }))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Queue a DMX update, replacing any update to the same universe which is
+    /// still waiting to be received.
+    fn queue_update(&mut self, data: DmxData) {
+        match self
+            .updates
+            .iter_mut()
+            .find(|queued| queued.universe == data.universe)
+        {
+            Some(queued) => *queued = data,
+            None => self.updates.push_back(data),
+        }
+    }
+
+    /// Take the oldest queued DMX update.
+    pub(crate) fn next_update(&mut self) -> Option<DmxData> {
+        self.updates.pop_front()
+    }
+
+    /// Whether any DMX updates are queued.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn has_updates(&self) -> bool {
+        !self.updates.is_empty()
+    }
+
+    pub(crate) fn set_max_frame_size(&mut self, size: usize) {
+        self.session.set_max_frame_size(size);
+    }
+
+    pub(crate) fn set_endianness(&mut self, endianness: Endianness) {
+        self.session.set_endianness(endianness);
+    }
+}
@@ -0,0 +1,223 @@
+use super::core::{with_deadline, Core, READ_CHUNK_SIZE};
+use super::{dmx_update, CallError, CallErrorKind};
+use crate::ola::Endianness;
+use crate::DmxBuffer;
+
+use std::future::poll_fn;
+use std::pin::Pin;
+use std::time::Duration;
+
+use async_io::Timer;
+use futures_io::{AsyncRead, AsyncWrite};
+
+/// An asynchronous client for OLA which isn't tied to a specific runtime.
+///
+/// This is the equivalent of [`ClientAsync`](super::ClientAsync) for streams
+/// implementing the `futures-io` traits, as used by async-std and smol (or
+/// any runtime through [`async_io::Async`]).
+#[derive(Debug)]
+pub struct ClientFutures<S: AsyncRead + AsyncWrite + Unpin> {
+    stream: S,
+    core: Core,
+    write_timeout: Option<Duration>,
+    call_timeout: Option<Duration>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> ClientFutures<S> {
+    /// Stream a DMX buffer to an OLA universe.
+    pub async fn send_dmx_streaming(
+        &mut self,
+        universe: u32,
+        data: &DmxBuffer,
+    ) -> Result<(), CallError> {
+        self.send_dmx_streaming_with_priority(universe, data, 100)
+            .await
+    }
+
+    /// Stream a DMX buffer to an OLA universe with a priority value.
+    pub async fn send_dmx_streaming_with_priority(
+        &mut self,
+        universe: u32,
+        data: &DmxBuffer,
+        priority: u8,
+    ) -> Result<(), CallError> {
+        self.core.send_dmx(universe, data, priority)?;
+
+        with_deadline(self.call_timeout, Timer::after, self.flush()).await
+    }
+
+    /// Stream DMX buffers to several OLA universes at once, as
+    /// `(universe, buffer, priority)`. See
+    /// [`ClientAsync::send_dmx_many`](super::ClientAsync::send_dmx_many).
+    pub async fn send_dmx_many(
+        &mut self,
+        frames: &[(u32, &DmxBuffer, u8)],
+    ) -> Result<(), CallError> {
+        self.core.send_dmx_many(frames)?;
+
+        with_deadline(self.call_timeout, Timer::after, self.flush()).await
+    }
+
+    /// Register for updates to an OLA universe. Updates are received with
+    /// [`recv`](Self::recv).
+    pub async fn register_universe(&mut self, universe: u32) -> Result<(), CallError> {
+        let id = self.core.register_universe(universe)?;
+
+        with_deadline(self.call_timeout, Timer::after, async {
+            self.flush().await?;
+            self.wait_response(id).await
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Close the connection with OLA. See
+    /// [`ClientAsync::close`](super::ClientAsync::close).
+    pub async fn close(mut self, blackout: bool) -> Result<(), CallError> {
+        self.core.close(blackout)?;
+
+        let deadline = self.call_timeout;
+        with_deadline(deadline, Timer::after, async move {
+            self.flush().await?;
+            poll_fn(|cx| Pin::new(&mut self.stream).poll_close(cx))
+                .await
+                .map_err(|e| self.core.fail(CallError::write(e)))
+        })
+        .await
+    }
+
+    /// Write everything queued by the session out to the stream.
+    ///
+    /// Written bytes are taken off the session as they go, so a write
+    /// abandoned part way through (by the call deadline) picks up where it
+    /// left off on the next call.
+    async fn flush(&mut self) -> Result<(), CallError> {
+        let stream = &mut self.stream;
+        let session = &mut self.core.session;
+        let write = async {
+            while !session.transmit().is_empty() {
+                let count = poll_fn(|cx| Pin::new(&mut *stream).poll_write(cx, session.transmit()))
+                    .await
+                    .map_err(CallError::write)?;
+                if count == 0 {
+                    return Err(CallError {
+                        kind: CallErrorKind::Disconnected,
+                    });
+                }
+                session.advance_transmit(count);
+            }
+
+            poll_fn(|cx| Pin::new(&mut *stream).poll_flush(cx))
+                .await
+                .map_err(CallError::write)
+        };
+        with_deadline(self.write_timeout, Timer::after, write)
+            .await
+            .map_err(|e| self.core.fail(e))
+    }
+
+    /// Wait until OLA answers the call with the given sequence number,
+    /// holding on to any DMX updates received in the meantime.
+    async fn wait_response(&mut self, id: u32) -> Result<Vec<u8>, CallError> {
+        loop {
+            let mut outcome = None;
+            self.core.dispatch(|response, result| {
+                if response == id {
+                    outcome = Some(result);
+                }
+            })?;
+            self.flush().await?;
+
+            match outcome {
+                Some(result) => return result,
+                None => match self.read().await {
+                    // a message which couldn't be decoded doesn't stop the
+                    // response from arriving
+                    Err(CallError {
+                        kind: CallErrorKind::Decode(_),
+                    }) if self.core.check().is_ok() => {}
+                    result => result?,
+                },
+            }
+        }
+    }
+
+    /// Wait for the next update to a registered universe. If a universe was
+    /// updated several times since the last call, only the latest update is
+    /// returned.
+    pub async fn recv(&mut self) -> Result<(i32, DmxBuffer), CallError> {
+        loop {
+            self.core.dispatch(|_, _| {})?;
+            // acknowledge any updates before handing them out
+            self.flush().await?;
+
+            match self.core.next_update() {
+                Some(data) => return dmx_update(data),
+                None => self.read().await?,
+            }
+        }
+    }
+
+    /// Read more data from the stream into the session.
+    async fn read(&mut self) -> Result<(), CallError> {
+        let mut chunk = [0; READ_CHUNK_SIZE];
+        let read = match poll_fn(|cx| Pin::new(&mut self.stream).poll_read(cx, &mut chunk)).await {
+            Ok(read) => read,
+            Err(e) => match CallError::read(e) {
+                // nothing was lost, so the call can be retried
+                e @ CallError {
+                    kind: CallErrorKind::Timeout,
+                } => return Err(e),
+                e => return Err(self.core.fail(e)),
+            },
+        };
+
+        if read == 0 {
+            return Err(self.core.fail(CallError {
+                kind: CallErrorKind::Disconnected,
+            }));
+        }
+
+        self.core.receive(&chunk[..read])
+    }
+
+    /// Set how long writing a message may block before the connection fails
+    /// with [`CallErrorKind::Timeout`]. `None` (the default) disables the
+    /// timeout.
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) {
+        self.write_timeout = timeout;
+    }
+
+    /// Set the deadline for completing an entire RPC call, after which it
+    /// fails with [`CallErrorKind::Timeout`]. `None` (the default) disables
+    /// the deadline. This does not apply to [`recv`](Self::recv), which waits
+    /// for updates for as long as it takes.
+    pub fn set_call_timeout(&mut self, timeout: Option<Duration>) {
+        self.call_timeout = timeout;
+    }
+
+    /// Set the largest message accepted from OLA, in bytes. See
+    /// [`Config::max_frame_size`](crate::config::Config::max_frame_size).
+    pub fn set_max_frame_size(&mut self, size: usize) {
+        self.core.set_max_frame_size(size);
+    }
+
+    /// Set the byte order of message headers. See
+    /// [`Config::endianness`](crate::config::Config::endianness).
+    pub fn set_endianness(&mut self, endianness: Endianness) {
+        self.core.set_endianness(endianness);
+    }
+
+    /// Construct a new async client from a `futures-io` stream. The client is
+    /// initialized with a fresh context. This usually shouldn't be called
+    /// directly as `ClientConfig::connect_async_io()` will set up a stream
+    /// for you before internally calling this.
+    pub fn from_stream(stream: S) -> Self {
+        Self {
+            stream,
+            core: Core::new(),
+            write_timeout: None,
+            call_timeout: None,
+        }
+    }
+}
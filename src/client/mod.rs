@@ -1,16 +1,39 @@
 #[cfg(feature = "tokio")]
 mod r#async;
+pub(crate) mod core;
+#[cfg(feature = "async-io")]
+pub(crate) mod futures;
+mod reconnect;
 mod sync;
 
+#[cfg(feature = "async-io")]
+pub use futures::ClientFutures;
 #[cfg(feature = "tokio")]
-pub use r#async::ClientAsync;
-pub use sync::StreamingClient;
+pub use r#async::{ClientAsync, PendingCall};
+pub use reconnect::ReconnectingClient;
+#[cfg(feature = "tokio")]
+pub use reconnect::ReconnectingClientAsync;
+pub use sync::{Shutdown, StreamingClient};
 
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
+use std::io::ErrorKind;
 
+use crate::ola::proto::DmxData;
 use crate::ola::{MessageDecodeError, MessageEncodeError};
-use crate::TryFromBufferError;
+use crate::packet::StartCode;
+use crate::session::Rejection;
+use crate::{DmxBuffer, TryFromBufferError};
+
+/// Convert a DMX update received from OLA into a universe and buffer.
+fn dmx_update(data: DmxData) -> Result<(i32, DmxBuffer), CallError> {
+    Ok((
+        data.universe,
+        data.data.try_into().map_err(|e| CallError {
+            kind: CallErrorKind::InvalidBuffer(e),
+        })?,
+    ))
+}
 
 /// The error type returned when an RCP call fails.
 #[derive(Debug)]
@@ -19,6 +42,64 @@ pub struct CallError {
     pub kind: CallErrorKind,
 }
 
+impl CallError {
+    /// Build an error from a failed write, distinguishing timeouts.
+    pub(crate) fn write(e: std::io::Error) -> Self {
+        let kind = match e.kind() {
+            ErrorKind::TimedOut | ErrorKind::WouldBlock => CallErrorKind::Timeout,
+            _ => CallErrorKind::Write(e),
+        };
+
+        Self { kind }
+    }
+
+    /// Build an error from a failed read, distinguishing timeouts.
+    pub(crate) fn read(e: std::io::Error) -> Self {
+        let kind = match e.kind() {
+            ErrorKind::TimedOut | ErrorKind::WouldBlock => CallErrorKind::Timeout,
+            _ => CallErrorKind::Read(e),
+        };
+
+        Self { kind }
+    }
+
+    /// Copy an error so it can be reported to several callers. I/O errors
+    /// keep their kind and message, but lose their source.
+    pub(crate) fn duplicate(&self) -> Self {
+        let kind = match &self.kind {
+            CallErrorKind::Encode(e) => CallErrorKind::Encode(e.clone()),
+            CallErrorKind::Decode(e) => CallErrorKind::Decode(e.clone()),
+            CallErrorKind::InvalidBuffer(e) => CallErrorKind::InvalidBuffer(e.clone()),
+            CallErrorKind::Write(e) => {
+                CallErrorKind::Write(std::io::Error::new(e.kind(), e.to_string()))
+            }
+            CallErrorKind::Read(e) => {
+                CallErrorKind::Read(std::io::Error::new(e.kind(), e.to_string()))
+            }
+            CallErrorKind::Rejected(e) => CallErrorKind::Rejected(e.clone()),
+            CallErrorKind::UnsupportedStartCode(e) => CallErrorKind::UnsupportedStartCode(*e),
+            CallErrorKind::Disconnected => CallErrorKind::Disconnected,
+            CallErrorKind::Timeout => CallErrorKind::Timeout,
+        };
+
+        Self { kind }
+    }
+
+    /// Whether the call failed because the connection to OLA was lost or left
+    /// in an unusable state (such as a frame header which can't be read).
+    ///
+    /// Timeouts aren't included, as a call which missed its deadline leaves
+    /// the connection usable. Write timeouts, which may leave a message
+    /// partially written, are handled by [`Core::fail`](core::Core::fail).
+    pub(crate) fn is_disconnect(&self) -> bool {
+        match &self.kind {
+            CallErrorKind::Decode(e) => e.is_framing(),
+            CallErrorKind::Write(_) | CallErrorKind::Read(_) | CallErrorKind::Disconnected => true,
+            _ => false,
+        }
+    }
+}
+
 impl Display for CallError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "failed to call RPC function")
@@ -32,6 +113,11 @@ impl Error for CallError {
             CallErrorKind::Decode(e) => Some(e),
             CallErrorKind::InvalidBuffer(e) => Some(e),
             CallErrorKind::Write(e) => Some(e),
+            CallErrorKind::Read(e) => Some(e),
+            CallErrorKind::Rejected(e) => Some(e),
+            CallErrorKind::UnsupportedStartCode(_) => None,
+            CallErrorKind::Disconnected => None,
+            CallErrorKind::Timeout => None,
         }
     }
 }
@@ -49,4 +135,16 @@ pub enum CallErrorKind {
     InvalidBuffer(TryFromBufferError),
     /// Failure writing an RPC message to the underlying socket.
     Write(std::io::Error),
+    /// Failure reading an RPC message from the underlying socket.
+    Read(std::io::Error),
+    /// OLA refused to answer the call.
+    Rejected(Rejection),
+    /// Packet had an alternate start code, which OLA can't transport.
+    UnsupportedStartCode(StartCode),
+    /// OLA closed the connection, either by sending a disconnect message or
+    /// by closing the socket.
+    Disconnected,
+    /// Call did not complete within the configured write timeout or call
+    /// deadline.
+    Timeout,
 }
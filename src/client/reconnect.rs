@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+#[cfg(feature = "tokio")]
+use std::collections::HashSet;
+use std::net::TcpStream;
+use std::thread::sleep;
+
+#[cfg(feature = "tokio")]
+use super::CallErrorKind;
+#[cfg(feature = "tokio")]
+use super::ClientAsync;
+use super::{CallError, StreamingClient};
+use crate::config::{Config, ConnectError, ConnectErrorKind};
+use crate::DmxBuffer;
+
+#[cfg(feature = "tokio")]
+use tokio::time::sleep as tokio_sleep;
+
+/// The last frame streamed to a universe, replayed after reconnecting.
+#[derive(Clone, Debug)]
+struct Frame {
+    data: DmxBuffer,
+    priority: u8,
+}
+
+fn record_frame(frames: &mut HashMap<u32, Frame>, universe: u32, data: &DmxBuffer, priority: u8) {
+    match frames.get_mut(&universe) {
+        Some(frame) => {
+            frame.data.clone_from(data);
+            frame.priority = priority;
+        }
+        None => {
+            let data = data.clone();
+            frames.insert(universe, Frame { data, priority });
+        }
+    }
+}
+
+/// The last frame of every universe, in the form taken by `send_dmx_many`.
+fn replay_frames(frames: &HashMap<u32, Frame>) -> Vec<(u32, &DmxBuffer, u8)> {
+    frames
+        .iter()
+        .map(|(universe, frame)| (*universe, &frame.data, frame.priority))
+        .collect()
+}
+
+/// A synchronous streaming client which reconnects to OLA when the
+/// connection is lost.
+///
+/// When a call fails because the connection to `olad` broke, the client
+/// reconnects using the [`Config`] it was created with, waiting between
+/// attempts according to `reconnect_backoff`. Once connected again, the last
+/// frame sent to each universe is re-sent so outputs recover without waiting
+/// for the next frame.
+///
+/// Reconnecting blocks until a connection succeeds or `reconnect_backoff`
+/// runs out of attempts, in which case the call returns the error that broke
+/// the connection. If `auto_start` is enabled, `olad` is started again (at
+/// most once per reconnect) when it has gone away.
+#[derive(Debug)]
+pub struct ReconnectingClient {
+    config: Config,
+    client: StreamingClient<TcpStream>,
+    frames: HashMap<u32, Frame>,
+}
+
+impl ReconnectingClient {
+    /// Connect to OLA with the given configuration. See [`Config::connect`].
+    pub fn connect(config: Config) -> Result<Self, ConnectError> {
+        let client = config.connect()?;
+
+        Ok(Self {
+            config,
+            client,
+            frames: HashMap::new(),
+        })
+    }
+
+    /// Send a DMX buffer to an OLA universe.
+    pub fn send_dmx(&mut self, universe: u32, data: &DmxBuffer) -> Result<(), CallError> {
+        self.send_dmx_with_priority(universe, data, 100)
+    }
+
+    /// Send a DMX buffer to an OLA universe with a priority value. If the
+    /// connection was lost, this reconnects and re-sends the buffer.
+    pub fn send_dmx_with_priority(
+        &mut self,
+        universe: u32,
+        data: &DmxBuffer,
+        priority: u8,
+    ) -> Result<(), CallError> {
+        record_frame(&mut self.frames, universe, data, priority);
+
+        match self.client.send_dmx_with_priority(universe, data, priority) {
+            Err(e) if self.client.is_failed() => self.reconnect().map_err(|_| e),
+            result => result,
+        }
+    }
+
+    /// Send DMX buffers to several OLA universes at once. See
+    /// [`StreamingClient::send_dmx_many`].
+    pub fn send_dmx_many(&mut self, frames: &[(u32, &DmxBuffer, u8)]) -> Result<(), CallError> {
+        for &(universe, data, priority) in frames {
+            record_frame(&mut self.frames, universe, data, priority);
+        }
+
+        match self.client.send_dmx_many(frames) {
+            Err(e) if self.client.is_failed() => self.reconnect().map_err(|_| e),
+            result => result,
+        }
+    }
+
+    /// Close the connection with OLA. See [`StreamingClient::close`].
+    pub fn close(self, blackout: bool) -> Result<(), CallError> {
+        self.client.close(blackout)
+    }
+
+    fn replay(&mut self) -> Result<(), CallError> {
+        self.client.send_dmx_many(&replay_frames(&self.frames))
+    }
+
+    /// Replace the connection with a new one, retrying with backoff until it
+    /// succeeds, and re-send the last frame of every universe. Fails with the
+    /// error of the last attempt once `reconnect_backoff` runs out of
+    /// attempts.
+    ///
+    /// This happens automatically when a call fails due to a lost connection,
+    /// but can also be triggered manually (for example, when a
+    /// [`Supervisor`](crate::olad::Supervisor) reports that `olad` was
+    /// restarted).
+    pub fn reconnect(&mut self) -> Result<(), ConnectError> {
+        let backoff = self.config.reconnect_backoff;
+        let mut config = self.config.clone();
+        let mut attempt = 0;
+        loop {
+            let error = match config.connect() {
+                Ok(client) => {
+                    self.client = client;
+                    match self.replay() {
+                        Ok(()) => return Ok(()),
+                        Err(e) => ConnectError {
+                            kind: ConnectErrorKind::Restore(e),
+                        },
+                    }
+                }
+                Err(e) => e,
+            };
+
+            attempt += 1;
+            if backoff.max_attempts.is_some_and(|max| attempt >= max) {
+                return Err(error);
+            }
+            // olad was started by the first attempt if it needed to be, so
+            // give it time to come up rather than starting another
+            config.auto_start = false;
+            sleep(backoff.delay(attempt - 1));
+        }
+    }
+}
+
+/// An asynchronous client which reconnects to OLA when the connection is
+/// lost.
+///
+/// This behaves like [`ReconnectingClient`], but also restores universe
+/// registrations after reconnecting, so [`recv`](Self::recv) keeps receiving
+/// updates.
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct ReconnectingClientAsync {
+    config: Config,
+    client: ClientAsync,
+    frames: HashMap<u32, Frame>,
+    registered: HashSet<u32>,
+}
+
+#[cfg(feature = "tokio")]
+impl ReconnectingClientAsync {
+    /// Connect to OLA with the given configuration. See
+    /// [`Config::connect_async`].
+    pub async fn connect(config: Config) -> Result<Self, ConnectError> {
+        let client = config.connect_async().await?;
+
+        Ok(Self {
+            config,
+            client,
+            frames: HashMap::new(),
+            registered: HashSet::new(),
+        })
+    }
+
+    /// Stream a DMX buffer to an OLA universe.
+    pub async fn send_dmx_streaming(
+        &mut self,
+        universe: u32,
+        data: &DmxBuffer,
+    ) -> Result<(), CallError> {
+        self.send_dmx_streaming_with_priority(universe, data, 100)
+            .await
+    }
+
+    /// Stream a DMX buffer to an OLA universe with a priority value. If the
+    /// connection was lost, this reconnects and re-sends the buffer.
+    pub async fn send_dmx_streaming_with_priority(
+        &mut self,
+        universe: u32,
+        data: &DmxBuffer,
+        priority: u8,
+    ) -> Result<(), CallError> {
+        record_frame(&mut self.frames, universe, data, priority);
+
+        match self
+            .client
+            .send_dmx_streaming_with_priority(universe, data, priority)
+            .await
+        {
+            Err(e) if self.client.is_failed() => self.reconnect().await.map_err(|_| e),
+            result => result,
+        }
+    }
+
+    /// Register for updates to a universe. The registration is restored
+    /// whenever the client reconnects.
+    pub async fn register_universe(&mut self, universe: u32) -> Result<(), CallError> {
+        if let Err(e) = self.client.register_universe(universe).await {
+            if !self.client.is_failed() {
+                return Err(e);
+            }
+            // the registration may not have reached olad, so make it again
+            self.reconnect().await.map_err(|_| e)?;
+            self.client.register_universe(universe).await?;
+        }
+        self.registered.insert(universe);
+
+        Ok(())
+    }
+
+    /// Wait for the next update to a registered universe, reconnecting if the
+    /// connection was lost.
+    pub async fn recv(&mut self) -> Result<(i32, DmxBuffer), CallError> {
+        loop {
+            match self.client.recv().await {
+                Err(e) if self.client.is_failed() => self.reconnect().await.map_err(|_| e)?,
+                result => return result,
+            }
+        }
+    }
+
+    /// Stream DMX buffers to several OLA universes at once. See
+    /// [`ClientAsync::send_dmx_many`].
+    pub async fn send_dmx_many(
+        &mut self,
+        frames: &[(u32, &DmxBuffer, u8)],
+    ) -> Result<(), CallError> {
+        for &(universe, data, priority) in frames {
+            record_frame(&mut self.frames, universe, data, priority);
+        }
+
+        match self.client.send_dmx_many(frames).await {
+            Err(e) if self.client.is_failed() => self.reconnect().await.map_err(|_| e),
+            result => result,
+        }
+    }
+
+    /// Close the connection with OLA. See [`ClientAsync::close`].
+    pub async fn close(self, blackout: bool) -> Result<(), CallError> {
+        self.client.close(blackout).await
+    }
+
+    async fn replay(&mut self) -> Result<(), CallError> {
+        let mut rejected = Vec::new();
+        for &universe in &self.registered {
+            match self.client.register_universe(universe).await {
+                Ok(()) => {}
+                // olad no longer accepts this registration, so stop restoring
+                // it rather than failing every reconnect
+                Err(CallError {
+                    kind: CallErrorKind::Rejected(_),
+                }) => rejected.push(universe),
+                Err(e) => return Err(e),
+            }
+        }
+        for universe in rejected {
+            self.registered.remove(&universe);
+        }
+
+        self.client
+            .send_dmx_many(&replay_frames(&self.frames))
+            .await
+    }
+
+    /// Replace the connection with a new one, retrying with backoff until it
+    /// succeeds, then restore universe registrations and re-send the last
+    /// frame of every universe. Registrations which `olad` rejects are
+    /// dropped. See [`ReconnectingClient::reconnect`].
+    pub async fn reconnect(&mut self) -> Result<(), ConnectError> {
+        let backoff = self.config.reconnect_backoff;
+        let mut config = self.config.clone();
+        let mut attempt = 0;
+        loop {
+            let error = match config.connect_async().await {
+                Ok(client) => {
+                    self.client = client;
+                    match self.replay().await {
+                        Ok(()) => return Ok(()),
+                        Err(e) => ConnectError {
+                            kind: ConnectErrorKind::Restore(e),
+                        },
+                    }
+                }
+                Err(e) => e,
+            };
+
+            attempt += 1;
+            if backoff.max_attempts.is_some_and(|max| attempt >= max) {
+                return Err(error);
+            }
+            // see ReconnectingClient::reconnect
+            config.auto_start = false;
+            tokio_sleep(backoff.delay(attempt - 1)).await;
+        }
+    }
+}
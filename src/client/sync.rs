@@ -1,16 +1,45 @@
-use std::io::Write;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{self, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
 
-use super::{CallError, CallErrorKind};
-use crate::ola::proto::{DmxData, OlaServerServiceCall};
-use crate::ola::RpcContext;
+use super::core::{Core, READ_CHUNK_SIZE};
+use super::{dmx_update, CallError, CallErrorKind};
+use crate::ola::Endianness;
 use crate::DmxBuffer;
 
-use bytes::BytesMut;
+/// A stream whose write half can be shut down, telling OLA that nothing more
+/// will be sent. Required by [`StreamingClient::close`].
+///
+/// This is implemented for sockets, and can be implemented for other
+/// transports (such as a TLS-wrapped stream) to close them cleanly.
+pub trait Shutdown {
+    /// Shut down the write half of the stream.
+    fn shutdown(&mut self) -> std::io::Result<()>;
+}
+
+impl Shutdown for TcpStream {
+    fn shutdown(&mut self) -> std::io::Result<()> {
+        TcpStream::shutdown(self, net::Shutdown::Write)
+    }
+}
+
+#[cfg(unix)]
+impl Shutdown for UnixStream {
+    fn shutdown(&mut self) -> std::io::Result<()> {
+        UnixStream::shutdown(self, net::Shutdown::Write)
+    }
+}
 
+/// A synchronous client for OLA.
+///
+/// Any stream implementing [`Write`] can be used to send DMX data. When the
+/// stream also implements [`Read`] (as sockets do), the client can register
+/// for and receive universe updates as well.
 #[derive(Debug)]
 pub struct StreamingClient<S: Write> {
     stream: S,
-    ctx: RpcContext,
+    core: Core,
 }
 
 impl<S: Write> StreamingClient<S> {
@@ -26,32 +55,173 @@ impl<S: Write> StreamingClient<S> {
         data: &DmxBuffer,
         priority: u8,
     ) -> Result<(), CallError> {
-        let request = OlaServerServiceCall::StreamDmxData(DmxData {
-            universe: universe as i32,
-            data: data.to_vec(),
-            priority: Some(priority as i32),
-        });
-
-        let mut buf = BytesMut::new();
-        self.ctx.encode(request, &mut buf).map_err(|e| CallError {
-            kind: CallErrorKind::Encode(e),
-        })?;
-        self.stream
-            .write_all(&buf.freeze())
-            .map_err(|e| CallError {
-                kind: CallErrorKind::Write(e),
-            })?;
+        self.core.send_dmx(universe, data, priority)?;
+
+        self.flush()
+    }
+
+    /// Send DMX buffers to several OLA universes at once, as
+    /// `(universe, buffer, priority)`. All buffers are written to the stream
+    /// together, so OLA receives them as one coherent frame.
+    pub fn send_dmx_many(&mut self, frames: &[(u32, &DmxBuffer, u8)]) -> Result<(), CallError> {
+        self.core.send_dmx_many(frames)?;
+
+        self.flush()
+    }
+
+    /// Whether the connection has become unusable, failing every call.
+    pub(crate) fn is_failed(&self) -> bool {
+        self.core.check().is_err()
+    }
+
+    /// Write everything queued by the session out to the stream.
+    fn flush(&mut self) -> Result<(), CallError> {
+        while !self.core.session.transmit().is_empty() {
+            match self.stream.write(self.core.session.transmit()) {
+                Ok(0) => {
+                    return Err(self.core.fail(CallError {
+                        kind: CallErrorKind::Disconnected,
+                    }))
+                }
+                Ok(count) => self.core.session.advance_transmit(count),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(self.core.fail(CallError::write(e))),
+            }
+        }
+
         Ok(())
     }
 
+    /// Set the largest message accepted from OLA, in bytes. See
+    /// [`Config::max_frame_size`](crate::config::Config::max_frame_size).
+    pub fn set_max_frame_size(&mut self, size: usize) {
+        self.core.set_max_frame_size(size);
+    }
+
+    /// Set the byte order of message headers. See
+    /// [`Config::endianness`](crate::config::Config::endianness).
+    pub fn set_endianness(&mut self, endianness: Endianness) {
+        self.core.set_endianness(endianness);
+    }
+
     /// Construct a new streaming client from an stream. The client is
     /// initialized with a fresh context. This usually, shouldn't be called
     /// directly, as `ClientConfig::connect()` will set up a stream for you
     /// before internally calling this.
+    ///
+    /// Any transport can be used, such as a `UnixStream`, a pipe to a
+    /// forwarding process or a TLS-wrapped stream.
+    ///
+    /// Writes are bounded by the stream's own timeout (see
+    /// [`TcpStream::set_write_timeout`](std::net::TcpStream::set_write_timeout)),
+    /// and fail with [`CallErrorKind::Timeout`] when it elapses. As OLA may
+    /// have received only part of a message, every later call fails too.
+    /// Reads are bounded by the stream's read timeout in the same way, but
+    /// as nothing is lost when one times out, the client stays usable.
     pub fn from_stream(stream: S) -> Self {
         Self {
             stream,
-            ctx: RpcContext::new(),
+            core: Core::new(),
         }
     }
 }
+
+impl<S: Write + Shutdown> StreamingClient<S> {
+    /// Close the connection with OLA.
+    ///
+    /// All universes registered with [`register_universe`] are unregistered
+    /// and any buffered writes are flushed before the write half of the
+    /// stream is shut down. If `blackout` is set, every universe this client
+    /// has sent to is sent a zeroed buffer first (at the priority last used
+    /// for it).
+    ///
+    /// [`register_universe`]: StreamingClient::register_universe
+    pub fn close(mut self, blackout: bool) -> Result<(), CallError> {
+        self.core.close(blackout)?;
+
+        self.flush()?;
+        self.stream
+            .flush()
+            .and_then(|_| self.stream.shutdown())
+            .map_err(|e| self.core.fail(CallError::write(e)))
+    }
+}
+
+impl<S: Read + Write> StreamingClient<S> {
+    /// Register for updates to an OLA universe. Blocks until OLA has
+    /// acknowledged the registration. Updates are received with
+    /// [`recv`](Self::recv).
+    pub fn register_universe(&mut self, universe: u32) -> Result<(), CallError> {
+        let id = self.core.register_universe(universe)?;
+        self.flush()?;
+
+        self.wait_response(id)?;
+        Ok(())
+    }
+
+    /// Block until OLA answers the call with the given sequence number,
+    /// holding on to any DMX updates received in the meantime.
+    fn wait_response(&mut self, id: u32) -> Result<Vec<u8>, CallError> {
+        loop {
+            let mut outcome = None;
+            self.core.dispatch(|response, result| {
+                if response == id {
+                    outcome = Some(result);
+                }
+            })?;
+            self.flush()?;
+
+            match outcome {
+                Some(result) => return result,
+                None => match self.read() {
+                    // a message which couldn't be decoded doesn't stop the
+                    // response from arriving
+                    Err(CallError {
+                        kind: CallErrorKind::Decode(_),
+                    }) if !self.is_failed() => {}
+                    result => result?,
+                },
+            }
+        }
+    }
+
+    /// Block until the next update to a registered universe arrives. If a
+    /// universe was updated several times since the last call, only the
+    /// latest update is returned.
+    pub fn recv(&mut self) -> Result<(i32, DmxBuffer), CallError> {
+        loop {
+            self.core.dispatch(|_, _| {})?;
+            // acknowledge any updates before handing them out
+            self.flush()?;
+
+            match self.core.next_update() {
+                Some(data) => return dmx_update(data),
+                None => self.read()?,
+            }
+        }
+    }
+
+    /// Read more data from the stream into the session.
+    fn read(&mut self) -> Result<(), CallError> {
+        let mut chunk = [0; READ_CHUNK_SIZE];
+        let read = loop {
+            match self.stream.read(&mut chunk) {
+                Ok(read) => break read,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                // nothing was lost, so the call can be retried
+                Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock) => {
+                    return Err(CallError::read(e))
+                }
+                Err(e) => return Err(self.core.fail(CallError::read(e))),
+            }
+        };
+
+        if read == 0 {
+            return Err(self.core.fail(CallError {
+                kind: CallErrorKind::Disconnected,
+            }));
+        }
+
+        self.core.receive(&chunk[..read])
+    }
+}
@@ -0,0 +1,255 @@
+//! Codecs for framing OLA RPC messages.
+//!
+//! These implement [`tokio_util::codec`]'s [`Decoder`] and [`Encoder`] traits,
+//! allowing a stream to OLA to be wrapped in a [`Framed`] sink and stream of
+//! messages. This is useful for composing the protocol with other middleware,
+//! or for implementing the server side of the protocol (such as a mock
+//! `olad` in tests).
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use ola::codec::ClientCodec;
+//! # use tokio::net::TcpStream;
+//! # use tokio_util::codec::Framed;
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let stream = TcpStream::connect("127.0.0.1:9010").await?;
+//! let mut framed = Framed::new(stream, ClientCodec::new());
+//! # Ok(()) }
+//! ```
+//!
+//! [`Framed`]: tokio_util::codec::Framed
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::marker::PhantomData;
+
+use crate::ola::proto::rpc::{RpcMessage, Type};
+use crate::ola::proto::{OlaClientServiceCall, OlaServerServiceCall};
+use crate::ola::{
+    Endianness, MessageDecodeError, MessageDecodeErrorKind, MessageEncodeError, RpcCall, RpcContext,
+};
+
+use bytes::BytesMut;
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Codec for framing raw RPC messages.
+///
+/// Messages are passed through as-is, leaving it up to the user to assign
+/// sequence numbers and interpret message types.
+#[derive(Clone, Debug, Default)]
+pub struct OlaCodec {
+    ctx: RpcContext,
+}
+
+impl OlaCodec {
+    /// Build a codec for a new session.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the largest message accepted by the decoder. See
+    /// [`RpcContext::set_max_frame_size`].
+    pub fn set_max_frame_size(&mut self, size: usize) {
+        self.ctx.set_max_frame_size(size);
+    }
+
+    /// Set the byte order of message headers. See
+    /// [`RpcContext::set_endianness`].
+    pub fn set_endianness(&mut self, endianness: Endianness) {
+        self.ctx.set_endianness(endianness);
+    }
+}
+
+impl Decoder for OlaCodec {
+    type Item = RpcMessage;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        self.ctx.decode_frame(src).map_err(|e| CodecError {
+            kind: CodecErrorKind::Decode(e),
+        })
+    }
+}
+
+impl Encoder<RpcMessage> for OlaCodec {
+    type Error = CodecError;
+
+    fn encode(&mut self, item: RpcMessage, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.ctx.encode_message(item, dst).map_err(|e| CodecError {
+            kind: CodecErrorKind::Encode(e),
+        })
+    }
+}
+
+/// A message decoded by an [`RpcCodec`].
+#[derive(Clone, Debug)]
+pub enum Item<D> {
+    /// A request for a method of `D`, along with the sequence number it
+    /// should be answered with.
+    Call(u32, D),
+    /// Any other message, such as a response, a cancellation or a request
+    /// for a method `D` doesn't know.
+    Message(RpcMessage),
+}
+
+/// Codec for framing typed RPC calls.
+///
+/// Outgoing calls of type `E` are assigned sequence numbers from the codec's
+/// context. Incoming requests are decoded as calls of type `D`, along with
+/// the sequence number they should be answered with. Every other message is
+/// passed through as-is, so responses and rejections can be matched up with
+/// the calls they answer.
+///
+/// Raw [`RpcMessage`]s (for example, responses) can also be encoded.
+pub struct RpcCodec<E, D> {
+    ctx: RpcContext,
+    calls: PhantomData<fn(E) -> D>,
+}
+
+/// Codec for the client side of the OLA protocol.
+pub type ClientCodec = RpcCodec<OlaServerServiceCall, OlaClientServiceCall>;
+
+/// Codec for the server (`olad`) side of the OLA protocol.
+pub type ServerCodec = RpcCodec<OlaClientServiceCall, OlaServerServiceCall>;
+
+impl<E, D> RpcCodec<E, D> {
+    /// Build a codec for a new session.
+    pub fn new() -> Self {
+        Self {
+            ctx: RpcContext::new(),
+            calls: PhantomData,
+        }
+    }
+
+    /// Set the largest message accepted by the decoder. See
+    /// [`RpcContext::set_max_frame_size`].
+    pub fn set_max_frame_size(&mut self, size: usize) {
+        self.ctx.set_max_frame_size(size);
+    }
+
+    /// Set the byte order of message headers. See
+    /// [`RpcContext::set_endianness`].
+    pub fn set_endianness(&mut self, endianness: Endianness) {
+        self.ctx.set_endianness(endianness);
+    }
+}
+
+impl<E, D> Default for RpcCodec<E, D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E, D> Clone for RpcCodec<E, D> {
+    fn clone(&self) -> Self {
+        Self {
+            ctx: self.ctx.clone(),
+            calls: PhantomData,
+        }
+    }
+}
+
+impl<E, D> fmt::Debug for RpcCodec<E, D> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RpcCodec").field("ctx", &self.ctx).finish()
+    }
+}
+
+impl<E, D: RpcCall> Decoder for RpcCodec<E, D> {
+    type Item = Item<D>;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let message = match self.ctx.decode_frame(src) {
+            Ok(Some(message)) => message,
+            Ok(None) => return Ok(None),
+            Err(e) => {
+                return Err(CodecError {
+                    kind: CodecErrorKind::Decode(e),
+                })
+            }
+        };
+
+        let request = matches!(
+            Type::from_i32(message.r#type),
+            Some(Type::Request | Type::StreamRequest)
+        );
+        if !request {
+            return Ok(Some(Item::Message(message)));
+        }
+
+        match D::from_message(message.clone()) {
+            Ok((id, call)) => Ok(Some(Item::Call(id, call))),
+            Err(MessageDecodeError {
+                kind: MessageDecodeErrorKind::Unrecognised,
+            }) => Ok(Some(Item::Message(message))),
+            Err(e) => Err(CodecError {
+                kind: CodecErrorKind::Decode(e),
+            }),
+        }
+    }
+}
+
+impl<E: RpcCall, D> Encoder<E> for RpcCodec<E, D> {
+    type Error = CodecError;
+
+    fn encode(&mut self, item: E, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.ctx.encode(item, dst).map_err(|e| CodecError {
+            kind: CodecErrorKind::Encode(e),
+        })
+    }
+}
+
+impl<E, D> Encoder<RpcMessage> for RpcCodec<E, D> {
+    type Error = CodecError;
+
+    fn encode(&mut self, item: RpcMessage, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.ctx.encode_message(item, dst).map_err(|e| CodecError {
+            kind: CodecErrorKind::Encode(e),
+        })
+    }
+}
+
+/// The error type returned when framing messages with a codec fails.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct CodecError {
+    pub kind: CodecErrorKind,
+}
+
+impl Display for CodecError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to frame API message")
+    }
+}
+
+impl Error for CodecError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            CodecErrorKind::Encode(e) => Some(e),
+            CodecErrorKind::Decode(e) => Some(e),
+            CodecErrorKind::Io(e) => Some(e),
+        }
+    }
+}
+
+impl From<std::io::Error> for CodecError {
+    fn from(e: std::io::Error) -> Self {
+        Self {
+            kind: CodecErrorKind::Io(e),
+        }
+    }
+}
+
+/// Enum to store the various types of errors that can occur when framing
+/// messages.
+#[derive(Debug)]
+pub enum CodecErrorKind {
+    /// Failure encoding an RPC message.
+    Encode(MessageEncodeError),
+    /// Failure decoding an RPC message.
+    Decode(MessageDecodeError),
+    /// Failure reading from or writing to the underlying stream.
+    Io(std::io::Error),
+}
@@ -1,21 +1,40 @@
 //! Connection configuration.
 
 use std::error::Error;
+use std::ffi::OsString;
 use std::fmt::{self, Display, Formatter};
-use std::net::TcpStream;
+use std::io::{self, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::thread::sleep;
 use std::time::Duration;
 
 #[cfg(feature = "tokio")]
 use crate::client::ClientAsync;
-use crate::client::StreamingClient;
+#[cfg(feature = "async-io")]
+use crate::client::{core::timeout as async_io_timeout, ClientFutures};
+use crate::client::{CallError, StreamingClient};
+use crate::ola::{Endianness, DEFAULT_MAX_FRAME_SIZE};
+use crate::olad::{OladHandle, OladLogger, Supervisor};
 
+#[cfg(feature = "async-io")]
+use async_io::{Async, Timer};
+#[cfg(feature = "async-io")]
+use futures_io::{AsyncRead as FuturesRead, AsyncWrite as FuturesWrite};
+#[cfg(all(unix, feature = "tokio"))]
+use tokio::net::UnixStream as TokioUnixStream;
 #[cfg(feature = "tokio")]
-use tokio::{net::TcpStream as TokioTcpStream, time::sleep as tokio_sleep};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream as TokioTcpStream,
+    time::{sleep as tokio_sleep, timeout as tokio_timeout},
+};
 
 const OLA_DEFAULT_PORT: u16 = 9010;
-const OLA_SPAWN_DELAY: Duration = Duration::from_secs(1);
+pub(crate) const OLA_SPAWN_DELAY: Duration = Duration::from_secs(1);
 
 /// Configuration for connecting to OLA.
 ///
@@ -23,6 +42,26 @@ const OLA_SPAWN_DELAY: Duration = Duration::from_secs(1);
 /// to OLA will be attempted on `127.0.0.1:9010`. If this fails, `olad` will be
 /// started on that port. Automatically starting OLA can be disabled by setting
 /// `auto_start` to false.
+///
+/// By default, connecting and calls are not subject to any timeouts. Set
+/// `connect_timeout`, `write_timeout` and `call_timeout` to bound how long
+/// these may block.
+///
+/// The remaining fields control how `olad` is launched when auto-starting,
+/// which allows running an isolated instance with its own configuration:
+///
+/// ```no_run
+/// # use ola::config::{Config, HttpServer, LogLevel};
+/// let config = Config {
+///     server_port: 9110,
+///     olad_path: "/opt/ola/bin/olad".into(),
+///     config_dir: Some("/var/lib/my-show/ola".into()),
+///     http: HttpServer::Disabled,
+///     log_level: Some(LogLevel::Warn),
+///     ..Config::new()
+/// };
+/// let client = config.connect();
+/// ```
 #[derive(Clone, Debug)]
 pub struct Config {
     /// Whether to auto-start `olad` if a connection to OLA cannot be made.
@@ -30,6 +69,61 @@ pub struct Config {
     /// What port OLA's RPC is listening on. This is also the port OLA will be
     /// configured to listen on if auto-started.
     pub server_port: u16,
+    /// How long to wait for a connection to OLA to be established before
+    /// giving up with [`ConnectErrorKind::Timeout`].
+    pub connect_timeout: Option<Duration>,
+    /// How long writing a message to OLA may block before failing with
+    /// [`CallErrorKind::Timeout`](crate::client::CallErrorKind::Timeout).
+    /// The connection can't be used after a write times out, as OLA may have
+    /// received only part of a message.
+    pub write_timeout: Option<Duration>,
+    /// Deadline for an entire RPC call of the asynchronous clients, including
+    /// waiting for OLA's reply. Calls exceeding it fail with
+    /// [`CallErrorKind::Timeout`](crate::client::CallErrorKind::Timeout).
+    ///
+    /// The synchronous client can't bound a whole call, so this is used as
+    /// its socket's read timeout instead: a call fails once OLA has been
+    /// silent for this long, and can be retried.
+    pub call_timeout: Option<Duration>,
+    /// Largest message accepted from OLA, in bytes. Receiving a larger one
+    /// fails with
+    /// [`MessageDecodeErrorKind::FrameTooLarge`](crate::ola::MessageDecodeErrorKind::FrameTooLarge)
+    /// instead of buffering it, which guards against corrupt or hostile
+    /// streams.
+    pub max_frame_size: usize,
+    /// Byte order of message headers. Only needs changing when `olad` runs
+    /// on a machine of different endianness, see [`Endianness`].
+    pub endianness: Endianness,
+    /// Path to the `olad` executable used when auto-starting. By default,
+    /// `olad` is looked up on the `PATH`.
+    pub olad_path: PathBuf,
+    /// Directory `olad` reads its configuration (including plugin settings)
+    /// from. If unset, `olad` uses its default of `~/.ola`.
+    pub config_dir: Option<PathBuf>,
+    /// Whether and where `olad` should serve its web interface.
+    pub http: HttpServer,
+    /// Logging verbosity of an auto-started `olad`. If unset, `olad` uses its
+    /// default level.
+    pub log_level: Option<LogLevel>,
+    /// Extra arguments passed to `olad` after all of the ones derived from
+    /// this configuration (including `--syslog` and `--daemon`), so they
+    /// take precedence when an option is given twice.
+    pub olad_args: Vec<OsString>,
+    /// Extra environment variables set for `olad`. These are added to the
+    /// environment inherited from the current process.
+    pub olad_env: Vec<(OsString, OsString)>,
+    /// Callback receiving the output of `olad` when started with
+    /// [`launch`](Self::launch). If unset, `olad` logs to syslog instead.
+    pub olad_log: Option<OladLogger>,
+    /// Whether `olad` should be killed when the [`OladHandle`] returned by
+    /// [`launch`](Self::launch) is dropped.
+    pub kill_on_drop: bool,
+    /// Delay between attempts to restart `olad` when it is being kept alive
+    /// by a [`Supervisor`].
+    pub restart_backoff: Backoff,
+    /// Delay between attempts to reconnect to OLA after losing the connection
+    /// in a [`ReconnectingClient`](crate::client::ReconnectingClient).
+    pub reconnect_backoff: Backoff,
 }
 
 impl Default for Config {
@@ -37,6 +131,21 @@ impl Default for Config {
         Self {
             auto_start: true,
             server_port: OLA_DEFAULT_PORT,
+            connect_timeout: None,
+            write_timeout: None,
+            call_timeout: None,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            endianness: Endianness::default(),
+            olad_path: PathBuf::from("olad"),
+            config_dir: None,
+            http: HttpServer::default(),
+            log_level: None,
+            olad_args: Vec::new(),
+            olad_env: Vec::new(),
+            olad_log: None,
+            kill_on_drop: false,
+            restart_backoff: Backoff::default(),
+            reconnect_backoff: Backoff::default(),
         }
     }
 }
@@ -48,33 +157,171 @@ impl Config {
         Default::default()
     }
 
-    fn spawn_olad(&self) -> Result<(), SpawnOladError> {
-        let mut command = Command::new("olad");
-        let command = command.args(["-r", &self.server_port.to_string(), "--syslog"]);
+    /// The command for starting `olad` as a child process, which logs to
+    /// syslog unless its output is captured by `olad_log`.
+    pub(crate) fn child_command(&self) -> Command {
+        match self.olad_log {
+            Some(_) => self.olad_command(&[]),
+            None => self.olad_command(&["--syslog"]),
+        }
+    }
+
+    /// Build the `olad` command line: the flags derived from this
+    /// configuration, then `flags`, then `olad_args`.
+    fn olad_command(&self, flags: &[&str]) -> Command {
+        let mut command = Command::new(&self.olad_path);
+        command.args(["-r", &self.server_port.to_string()]);
+
+        if let Some(config_dir) = &self.config_dir {
+            command.arg("-c").arg(config_dir);
+        }
+        match self.http {
+            HttpServer::Default => {}
+            HttpServer::Port(port) => {
+                command.args(["-d", &port.to_string()]);
+            }
+            HttpServer::Disabled => {
+                command.arg("--no-http");
+            }
+        }
+        if let Some(level) = self.log_level {
+            command.args(["-l", &(level as u8).to_string()]);
+        }
+        command.args(flags);
+
+        command.args(&self.olad_args);
+        command.envs(self.olad_env.iter().map(|(k, v)| (k, v)));
+
+        command
+    }
 
+    fn spawn_olad(&self) -> Result<(), SpawnOladError> {
         #[cfg(not(target_os = "windows"))]
-        let command = command.arg("--daemon");
+        let flags = ["--syslog", "--daemon"];
+        #[cfg(target_os = "windows")]
+        let flags = ["--syslog"];
 
-        command.spawn().map_err(SpawnOladError)?;
+        self.olad_command(&flags).spawn().map_err(SpawnOladError)?;
 
         Ok(())
     }
 
+    /// Start `olad` as a child of the current process.
+    ///
+    /// Unlike auto-starting, `olad` isn't daemonized. Its output is passed to
+    /// `olad_log` (if set) and it can be stopped through the returned handle.
+    /// This blocks briefly to give `olad` time to start listening, after which
+    /// a client can be connected with `auto_start` disabled.
+    ///
+    /// ```no_run
+    /// # use ola::config::Config;
+    /// # use ola::olad::OladLogger;
+    /// # use std::time::Duration;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let config = Config {
+    ///     auto_start: false,
+    ///     olad_log: Some(OladLogger::new(|_, line| println!("{line}"))),
+    ///     kill_on_drop: true,
+    ///     ..Config::new()
+    /// };
+    /// let mut olad = config.launch()?;
+    /// let client = config.connect()?;
+    ///
+    /// // ...
+    ///
+    /// drop(client);
+    /// olad.shutdown(Duration::from_secs(5))?;
+    /// # Ok(()) }
+    /// ```
+    pub fn launch(&self) -> Result<OladHandle, SpawnOladError> {
+        let handle = OladHandle::spawn(
+            self.child_command(),
+            self.olad_log.as_ref(),
+            self.kill_on_drop,
+        )
+        .map_err(SpawnOladError)?;
+        sleep(OLA_SPAWN_DELAY);
+
+        Ok(handle)
+    }
+
+    /// Start `olad` as a child of the current process and keep it running.
+    ///
+    /// This behaves like [`launch`](Self::launch), but the returned
+    /// [`Supervisor`] restarts `olad` with exponential backoff (see
+    /// `restart_backoff`) if it ever exits on its own.
+    ///
+    /// ```no_run
+    /// # use ola::config::Config;
+    /// # use ola::olad::SupervisorEvent;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let config = Config {
+    ///     auto_start: false,
+    ///     ..Config::new()
+    /// };
+    /// let supervisor = config.supervise()?;
+    /// let events = supervisor.subscribe();
+    /// let mut client = config.connect()?;
+    ///
+    /// for event in events {
+    ///     if let SupervisorEvent::Restarted = event {
+    ///         client = config.connect()?;
+    ///     }
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub fn supervise(&self) -> Result<Supervisor, SpawnOladError> {
+        let olad = self.launch()?;
+
+        Ok(Supervisor::start(self.clone(), olad))
+    }
+
+    fn endpoint(&self) -> SocketAddr {
+        SocketAddr::from((Ipv4Addr::LOCALHOST, self.server_port))
+    }
+
+    fn connect_tcp(&self) -> Result<TcpStream, ConnectError> {
+        let stream = match self.connect_timeout {
+            Some(timeout) => TcpStream::connect_timeout(&self.endpoint(), timeout),
+            None => TcpStream::connect(self.endpoint()),
+        }
+        .map_err(|e| ConnectError {
+            kind: match e.kind() {
+                io::ErrorKind::TimedOut => ConnectErrorKind::Timeout,
+                _ => ConnectErrorKind::Connect(e),
+            },
+        })?;
+
+        stream.set_nodelay(true).map_err(|e| ConnectError {
+            kind: ConnectErrorKind::NoDelay(e),
+        })?;
+        stream
+            .set_write_timeout(self.write_timeout)
+            .and_then(|_| stream.set_read_timeout(self.call_timeout))
+            .map_err(|e| ConnectError {
+                kind: ConnectErrorKind::SetTimeout(e),
+            })?;
+
+        Ok(stream)
+    }
+
+    fn client<S: Write>(&self, stream: S) -> StreamingClient<S> {
+        let mut client = StreamingClient::from_stream(stream);
+        client.set_max_frame_size(self.max_frame_size);
+        client.set_endianness(self.endianness);
+
+        client
+    }
+
     /// Connect to OLA with the synchronous client. Fails if a connection
     /// cannot be established and (when `auto_start` is enabled) if `olad`
     /// cannot be started.
     pub fn connect(&self) -> Result<StreamingClient<TcpStream>, ConnectError> {
-        let endpoint = ("127.0.0.1", self.server_port);
-
         if self.auto_start {
-            let stream = TcpStream::connect(endpoint);
+            let stream = self.connect_tcp();
 
             if let Ok(stream) = stream {
-                stream.set_nodelay(true).map_err(|e| ConnectError {
-                    kind: ConnectErrorKind::NoDelay(e),
-                })?;
-
-                return Ok(StreamingClient::from_stream(stream));
+                return Ok(self.client(stream));
             } else {
                 self.spawn_olad().map_err(|e| ConnectError {
                     kind: ConnectErrorKind::Spawn(e),
@@ -83,32 +330,57 @@ impl Config {
             }
         }
 
-        let stream = TcpStream::connect(endpoint).map_err(|e| ConnectError {
+        let stream = self.connect_tcp()?;
+
+        Ok(self.client(stream))
+    }
+
+    #[cfg(feature = "tokio")]
+    async fn connect_tcp_async(&self) -> Result<TokioTcpStream, ConnectError> {
+        let connect = TokioTcpStream::connect(self.endpoint());
+        let stream = match self.connect_timeout {
+            Some(timeout) => tokio_timeout(timeout, connect)
+                .await
+                .map_err(|_| ConnectError {
+                    kind: ConnectErrorKind::Timeout,
+                })?,
+            None => connect.await,
+        }
+        .map_err(|e| ConnectError {
             kind: ConnectErrorKind::Connect(e),
         })?;
+
         stream.set_nodelay(true).map_err(|e| ConnectError {
             kind: ConnectErrorKind::NoDelay(e),
         })?;
 
-        Ok(StreamingClient::from_stream(stream))
+        Ok(stream)
+    }
+
+    #[cfg(feature = "tokio")]
+    fn client_async<S>(&self, stream: S) -> ClientAsync
+    where
+        S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        let client = ClientAsync::from_stream(stream);
+        client.set_write_timeout(self.write_timeout);
+        client.set_call_timeout(self.call_timeout);
+        client.set_max_frame_size(self.max_frame_size);
+        client.set_endianness(self.endianness);
+
+        client
     }
 
     /// Connect to OLA with the asynchronous client. Fails if a connection
     /// cannot be established and (when `auto_start` is enabled) if `olad`
     /// cannot be started.
     #[cfg(feature = "tokio")]
-    pub async fn connect_async(&self) -> Result<ClientAsync<TokioTcpStream>, ConnectError> {
-        let endpoint = ("127.0.0.1", self.server_port);
-
+    pub async fn connect_async(&self) -> Result<ClientAsync, ConnectError> {
         if self.auto_start {
-            let stream = TokioTcpStream::connect(endpoint).await;
+            let stream = self.connect_tcp_async().await;
 
             if let Ok(stream) = stream {
-                stream.set_nodelay(true).map_err(|e| ConnectError {
-                    kind: ConnectErrorKind::NoDelay(e),
-                })?;
-
-                return Ok(ClientAsync::from_stream(stream));
+                return Ok(self.client_async(stream));
             } else {
                 self.spawn_olad().map_err(|e| ConnectError {
                     kind: ConnectErrorKind::Spawn(e),
@@ -117,19 +389,227 @@ impl Config {
             }
         }
 
-        let stream = TokioTcpStream::connect(endpoint)
-            .await
+        let stream = self.connect_tcp_async().await?;
+
+        Ok(self.client_async(stream))
+    }
+
+    /// Connect to OLA over a Unix domain socket with the synchronous client.
+    ///
+    /// This is intended for setups where `olad` is reachable through a local
+    /// socket proxy. `olad` itself only listens on TCP, so it is never
+    /// auto-started by this.
+    #[cfg(unix)]
+    pub fn connect_unix(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<StreamingClient<UnixStream>, ConnectError> {
+        let stream = UnixStream::connect(path).map_err(|e| ConnectError {
+            kind: ConnectErrorKind::Connect(e),
+        })?;
+        stream
+            .set_write_timeout(self.write_timeout)
+            .and_then(|_| stream.set_read_timeout(self.call_timeout))
+            .map_err(|e| ConnectError {
+                kind: ConnectErrorKind::SetTimeout(e),
+            })?;
+
+        Ok(self.client(stream))
+    }
+
+    /// Connect to OLA over a Unix domain socket with the asynchronous client.
+    /// See [`connect_unix`](Self::connect_unix).
+    #[cfg(all(unix, feature = "tokio"))]
+    pub async fn connect_unix_async(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<ClientAsync, ConnectError> {
+        let connect = TokioUnixStream::connect(path);
+        let stream = match self.connect_timeout {
+            Some(timeout) => tokio_timeout(timeout, connect)
+                .await
+                .map_err(|_| ConnectError {
+                    kind: ConnectErrorKind::Timeout,
+                })?,
+            None => connect.await,
+        }
+        .map_err(|e| ConnectError {
+            kind: ConnectErrorKind::Connect(e),
+        })?;
+
+        Ok(self.client_async(stream))
+    }
+
+    #[cfg(feature = "async-io")]
+    async fn connect_tcp_async_io(&self) -> Result<Async<TcpStream>, ConnectError> {
+        let connect = Async::<TcpStream>::connect(self.endpoint());
+        let stream = match self.connect_timeout {
+            Some(timeout) => async_io_timeout(Timer::after(timeout), connect)
+                .await
+                .ok_or(ConnectError {
+                    kind: ConnectErrorKind::Timeout,
+                })?,
+            None => connect.await,
+        }
+        .map_err(|e| ConnectError {
+            kind: ConnectErrorKind::Connect(e),
+        })?;
+
+        stream
+            .get_ref()
+            .set_nodelay(true)
             .map_err(|e| ConnectError {
-                kind: ConnectErrorKind::Connect(e),
+                kind: ConnectErrorKind::NoDelay(e),
             })?;
-        stream.set_nodelay(true).map_err(|e| ConnectError {
-            kind: ConnectErrorKind::NoDelay(e),
+
+        Ok(stream)
+    }
+
+    #[cfg(feature = "async-io")]
+    fn client_futures<S: FuturesRead + FuturesWrite + Unpin>(&self, stream: S) -> ClientFutures<S> {
+        let mut client = ClientFutures::from_stream(stream);
+        client.set_write_timeout(self.write_timeout);
+        client.set_call_timeout(self.call_timeout);
+        client.set_max_frame_size(self.max_frame_size);
+        client.set_endianness(self.endianness);
+
+        client
+    }
+
+    /// Connect to OLA with the runtime-independent asynchronous client, for
+    /// use with async-std, smol or any other executor. Fails if a connection
+    /// cannot be established and (when `auto_start` is enabled) if `olad`
+    /// cannot be started.
+    #[cfg(feature = "async-io")]
+    pub async fn connect_async_io(&self) -> Result<ClientFutures<Async<TcpStream>>, ConnectError> {
+        if self.auto_start {
+            let stream = self.connect_tcp_async_io().await;
+
+            if let Ok(stream) = stream {
+                return Ok(self.client_futures(stream));
+            } else {
+                self.spawn_olad().map_err(|e| ConnectError {
+                    kind: ConnectErrorKind::Spawn(e),
+                })?;
+                Timer::after(OLA_SPAWN_DELAY).await;
+            }
+        }
+
+        let stream = self.connect_tcp_async_io().await?;
+
+        Ok(self.client_futures(stream))
+    }
+
+    /// Connect to OLA over a Unix domain socket with the runtime-independent
+    /// asynchronous client. See [`connect_unix`](Self::connect_unix).
+    #[cfg(all(unix, feature = "async-io"))]
+    pub async fn connect_unix_async_io(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<ClientFutures<Async<UnixStream>>, ConnectError> {
+        let connect = Async::<UnixStream>::connect(path);
+        let stream = match self.connect_timeout {
+            Some(timeout) => async_io_timeout(Timer::after(timeout), connect)
+                .await
+                .ok_or(ConnectError {
+                    kind: ConnectErrorKind::Timeout,
+                })?,
+            None => connect.await,
+        }
+        .map_err(|e| ConnectError {
+            kind: ConnectErrorKind::Connect(e),
         })?;
 
-        Ok(ClientAsync::from_stream(stream))
+        Ok(self.client_futures(stream))
+    }
+}
+
+/// Exponentially increasing delay between retries.
+///
+/// The first retry happens after `initial`, with each following delay being
+/// `multiplier` times the previous one, up to at most `max`. Reconnecting
+/// clients give up after `max_attempts`, while a
+/// [`Supervisor`](crate::olad::Supervisor) keeps restarting `olad` for as long
+/// as it runs.
+///
+/// # Examples
+///
+/// ```
+/// # use ola::config::Backoff;
+/// # use std::time::Duration;
+/// let backoff = Backoff {
+///     initial: Duration::from_millis(100),
+///     max: Duration::from_secs(1),
+///     multiplier: 2,
+///     max_attempts: Some(5),
+/// };
+///
+/// assert_eq!(backoff.delay(0), Duration::from_millis(100));
+/// assert_eq!(backoff.delay(3), Duration::from_millis(800));
+/// assert_eq!(backoff.delay(4), Duration::from_secs(1));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Backoff {
+    /// Delay before the first retry.
+    pub initial: Duration,
+    /// Upper bound on the delay between retries.
+    pub max: Duration,
+    /// Factor the delay grows by after each retry.
+    pub multiplier: u32,
+    /// How many attempts are made (including the first) before giving up.
+    /// `None` retries forever.
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(500),
+            max: Duration::from_secs(30),
+            multiplier: 2,
+            max_attempts: Some(10),
+        }
     }
 }
 
+impl Backoff {
+    /// The delay before retry number `attempt`, counting from zero.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = self.multiplier.checked_pow(attempt).unwrap_or(u32::MAX);
+
+        self.initial
+            .checked_mul(factor)
+            .map_or(self.max, |delay| delay.min(self.max))
+    }
+}
+
+/// Configuration of `olad`'s built-in web interface.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HttpServer {
+    /// Serve the web interface on `olad`'s default port (9090).
+    #[default]
+    Default,
+    /// Serve the web interface on the given port.
+    Port(u16),
+    /// Don't start the web interface (`--no-http`).
+    Disabled,
+}
+
+/// Logging verbosity of `olad`, as passed to its `--log-level` option.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    /// Disable logging.
+    None = 0,
+    /// Only log fatal errors.
+    Fatal = 1,
+    /// Log warnings and above.
+    Warn = 2,
+    /// Log informational messages and above.
+    Info = 3,
+    /// Log everything, including debugging output.
+    Debug = 4,
+}
+
 /// The error type returned when connecting to OLA fails.
 #[derive(Debug)]
 #[non_exhaustive]
@@ -148,7 +628,10 @@ impl Error for ConnectError {
         match &self.kind {
             ConnectErrorKind::Connect(e) => Some(e),
             ConnectErrorKind::NoDelay(e) => Some(e),
+            ConnectErrorKind::SetTimeout(e) => Some(e),
             ConnectErrorKind::Spawn(e) => Some(e),
+            ConnectErrorKind::Timeout => None,
+            ConnectErrorKind::Restore(e) => Some(e),
         }
     }
 }
@@ -157,17 +640,25 @@ impl Error for ConnectError {
 /// OLA to fail.
 #[derive(Debug)]
 pub enum ConnectErrorKind {
-    /// Unable to establish TCP connection with OLA.
+    /// Unable to establish a connection with OLA.
     Connect(std::io::Error),
     /// Problem while setting `TCP_NODELAY` on the underlying socket.
     NoDelay(std::io::Error),
+    /// Problem while setting the read or write timeout on the underlying
+    /// socket.
+    SetTimeout(std::io::Error),
     /// Failure while attempting to auto-start `olad`.
     Spawn(SpawnOladError),
+    /// Connection was not established within the configured timeout.
+    Timeout,
+    /// Connected, but restoring a reconnecting client's streamed frames or
+    /// registrations over the new connection failed.
+    Restore(CallError),
 }
 
 /// The error type returned when spawning `olad` fails.
 #[derive(Debug)]
-pub struct SpawnOladError(std::io::Error);
+pub struct SpawnOladError(pub(crate) std::io::Error);
 
 impl Display for SpawnOladError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
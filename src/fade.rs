@@ -0,0 +1,222 @@
+//! Crossfading between DMX buffers.
+//!
+//! [`Crossfade`] interpolates every channel from one [`DmxBuffer`] to
+//! another along a [`Curve`]. Channels which shouldn't fade (such as gobo
+//! wheels or fixture modes) can be made to snap instead, and channels making
+//! up a 16-bit coarse/fine pair can be interpolated as a single value so the
+//! fine channel doesn't wrap around during the fade. For a plain linear fade,
+//! see [`DmxBuffer::crossfade`].
+//!
+//! # Examples
+//!
+//! ```
+//! # use ola::fade::{Crossfade, Curve};
+//! # use ola::DmxBuffer;
+//! let fade = Crossfade::new()
+//!     .curve(Curve::EaseInOut)
+//!     .wide(1) // pan on channels 1 and 2
+//!     .snap(3..=3); // gobo on channel 3
+//!
+//! let mut from = DmxBuffer::new();
+//! from.set_u16(1, 0x00ff);
+//! let mut to = DmxBuffer::new();
+//! to.set_u16(1, 0x0101);
+//! to.set_channel(3, 40);
+//! to.set_channel(4, 200);
+//!
+//! let halfway = fade.apply(&from, &to, 0.5);
+//! assert_eq!(halfway.get_u16(1), Some(0x0100));
+//! assert_eq!(halfway.channel(3), Some(40));
+//! assert_eq!(halfway.channel(4), Some(100));
+//! ```
+
+use std::ops::RangeBounds;
+
+use crate::buffer::{slot_range, MAX_SLOTS};
+use crate::DmxBuffer;
+
+/// The shape of a fade, mapping the position in the fade (from 0 to 1) to
+/// how far values have moved towards their target (from 0 to 1).
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Curve {
+    /// Move at a constant rate.
+    #[default]
+    Linear,
+    /// Start slowly and speed up (quadratic).
+    EaseIn,
+    /// Start quickly and slow down (quadratic).
+    EaseOut,
+    /// Start and end slowly (smoothstep).
+    EaseInOut,
+    /// A custom curve. Results outside of 0 to 1 overshoot the fade, but
+    /// channel values are kept within their range.
+    Custom(fn(f32) -> f32),
+}
+
+impl Curve {
+    /// Map a position in the fade to the progress of the values. `position`
+    /// is clamped to between 0 and 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ola::fade::Curve;
+    /// assert_eq!(Curve::Linear.apply(0.25), 0.25);
+    /// assert_eq!(Curve::EaseIn.apply(0.5), 0.25);
+    /// assert_eq!(Curve::EaseOut.apply(0.5), 0.75);
+    /// assert_eq!(Curve::EaseInOut.apply(2.0), 1.0);
+    /// ```
+    pub fn apply(self, position: f32) -> f32 {
+        let t = if position.is_nan() {
+            0.0
+        } else {
+            position.clamp(0.0, 1.0)
+        };
+
+        match self {
+            Curve::Linear => t,
+            Curve::EaseIn => t * t,
+            Curve::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Curve::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Curve::Custom(curve) => curve(t),
+        }
+    }
+}
+
+/// A set of slots, one bit per slot.
+type SlotSet = [u64; MAX_SLOTS / 64];
+
+fn contains(set: &SlotSet, slot: usize) -> bool {
+    set[slot / 64] & (1 << (slot % 64)) != 0
+}
+
+fn insert(set: &mut SlotSet, slot: usize) {
+    set[slot / 64] |= 1 << (slot % 64);
+}
+
+/// Interpolate between two values, rounding to the nearest step.
+fn interpolate(from: u16, to: u16, progress: f32, max: u16) -> u16 {
+    let value = f32::from(from) + (f32::from(to) - f32::from(from)) * progress;
+
+    value.round().clamp(0.0, f32::from(max)) as u16
+}
+
+/// Settings for crossfading between two DMX buffers.
+///
+/// Channels are numbered from 1, like in
+/// [`DmxBuffer::channel`](crate::DmxBuffer::channel). See the
+/// [module documentation](self) for an example.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Crossfade {
+    curve: Curve,
+    snap: SlotSet,
+    wide: SlotSet,
+}
+
+impl Crossfade {
+    /// A linear fade of every channel.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Fade along `curve` instead of linearly.
+    pub fn curve(mut self, curve: Curve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    /// Don't fade a range of channels. Instead, they jump to their target
+    /// value as soon as the fade starts. Channels outside of a universe are
+    /// ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ola::fade::Crossfade;
+    /// # use ola::DmxBuffer;
+    /// let fade = Crossfade::new().snap(2..);
+    /// let to = DmxBuffer::from([100; 512]);
+    ///
+    /// let start = fade.apply(&DmxBuffer::new(), &to, 0.0);
+    /// assert_eq!(start.channels(1..=2), Some(&[0, 0][..]));
+    ///
+    /// let started = fade.apply(&DmxBuffer::new(), &to, 0.1);
+    /// assert_eq!(started.channels(1..=2), Some(&[10, 100][..]));
+    /// ```
+    pub fn snap(mut self, channels: impl RangeBounds<usize>) -> Self {
+        if let Some(range) = slot_range(channels, MAX_SLOTS) {
+            for slot in range.start..range.end.min(MAX_SLOTS) {
+                insert(&mut self.snap, slot);
+            }
+        }
+        self
+    }
+
+    /// Fade `channel` and the channel after it as one 16-bit value, with the
+    /// coarse byte in `channel` and the fine byte after it (see
+    /// [`DmxBuffer::get_u16`](crate::DmxBuffer::get_u16)). If the pair is
+    /// also snapped, the coarse channel decides whether it snaps.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel` is not between 1 and 511.
+    pub fn wide(mut self, channel: usize) -> Self {
+        assert!(
+            (1..MAX_SLOTS).contains(&channel),
+            "a 16-bit pair must start between channel 1 and 511, not {channel}"
+        );
+
+        insert(&mut self.wide, channel - 1);
+        self
+    }
+
+    /// Crossfade from `from` to `to`, with `position` going from 0 (all
+    /// `from`) to 1 (all `to`). The result is as long as the longer of the
+    /// two buffers, with missing slots fading from or to zero.
+    pub fn apply(&self, from: &DmxBuffer, to: &DmxBuffer, position: f32) -> DmxBuffer {
+        let mut out = DmxBuffer::new();
+        self.apply_into(from, to, position, &mut out);
+
+        out
+    }
+
+    /// Crossfade from `from` to `to` into an existing buffer, avoiding an
+    /// allocation for every step of the fade. See [`apply`](Self::apply).
+    pub fn apply_into(&self, from: &DmxBuffer, to: &DmxBuffer, position: f32, out: &mut DmxBuffer) {
+        let slots = from.slots().max(to.slots());
+        out.set_slots(slots);
+
+        let progress = self.curve.apply(position);
+        let started = position > 0.0;
+        let from = |slot: usize| from.as_slice().get(slot).copied().unwrap_or(0);
+        let to = |slot: usize| to.as_slice().get(slot).copied().unwrap_or(0);
+
+        let mut slot = 0;
+        while slot < slots {
+            let wide = contains(&self.wide, slot) && slot + 1 < slots;
+            let snap = contains(&self.snap, slot);
+
+            if wide {
+                let start = u16::from_be_bytes([from(slot), from(slot + 1)]);
+                let end = u16::from_be_bytes([to(slot), to(slot + 1)]);
+                let value = match snap {
+                    true if started => end,
+                    true => start,
+                    false => interpolate(start, end, progress, u16::MAX),
+                };
+                out.as_mut_slice()[slot..slot + 2].copy_from_slice(&value.to_be_bytes());
+                slot += 2;
+            } else {
+                out[slot] = match snap {
+                    true if started => to(slot),
+                    true => from(slot),
+                    false => {
+                        let value = interpolate(from(slot).into(), to(slot).into(), progress, 255);
+                        value as u8
+                    }
+                };
+                slot += 1;
+            }
+        }
+    }
+}
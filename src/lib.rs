@@ -6,25 +6,39 @@
 //! ## Feature flags
 //!
 //! * **`tokio`** *(enabled by default)* — Enable the Tokio-powered asynchronous client
+//! * **`async-io`** — Enable a runtime-independent asynchronous client built on `futures-io`, for use with async-std and smol
+//! * **`codec`** — Enable `tokio_util::codec` implementations for framing RPC messages
+//! * **`serde`** — Enable `Serialize` and `Deserialize` implementations for [`DmxBuffer`], the protocol messages and other returned types, and the `channel_map` representation
 
 mod buffer;
+#[cfg(feature = "serde")]
+pub mod channel_map;
 pub mod client;
+#[cfg(feature = "codec")]
+pub mod codec;
 pub mod config;
+pub mod fade;
+pub mod merge;
 pub mod ola;
+pub mod olad;
+pub mod packet;
+pub mod session;
 
 const PROTOCOL_VERSION: u32 = 1;
 const VERSION_MASK: u32 = 0xf0000000;
 const SIZE_MASK: u32 = 0x0fffffff;
 
-pub use buffer::{DmxBuffer, TryFromBufferError};
+pub use buffer::{ChangedRanges, ChannelError, Diff, DmxBuffer, TryFromBufferError};
 
+#[cfg(feature = "async-io")]
+use async_io::Async;
 #[cfg(feature = "tokio")]
 use client::ClientAsync;
+#[cfg(feature = "async-io")]
+use client::ClientFutures;
 use client::StreamingClient;
 use config::{Config, ConnectError};
 use std::net::TcpStream;
-#[cfg(feature = "tokio")]
-use tokio::net::TcpStream as TokioTcpStream;
 
 /// Start a synchronous connection with OLA.
 ///
@@ -41,6 +55,16 @@ pub fn connect() -> Result<StreamingClient<TcpStream>, ConnectError> {
 /// configuration (auto-start and default port). See [`Config`] for changing
 /// the port and auto-start behavior.
 #[cfg(feature = "tokio")]
-pub async fn connect_async() -> Result<ClientAsync<TokioTcpStream>, ConnectError> {
+pub async fn connect_async() -> Result<ClientAsync, ConnectError> {
     Config::new().connect_async().await
 }
+
+/// Start a runtime-independent asynchronous connection with OLA.
+///
+/// This is a convenience function for connecting to OLA using the default
+/// configuration (auto-start and default port). See [`Config`] for changing
+/// the port and auto-start behavior.
+#[cfg(feature = "async-io")]
+pub async fn connect_async_io() -> Result<ClientFutures<Async<TcpStream>>, ConnectError> {
+    Config::new().connect_async_io().await
+}
@@ -0,0 +1,347 @@
+//! Merging DMX from several sources into one universe.
+//!
+//! When more than one source sends to the same universe, `olad` combines
+//! them according to the universe's [`MergeMode`]. [`Merger`] applies the
+//! same rules client-side, for example to combine several internal effect
+//! generators before streaming the result to OLA.
+//!
+//! # Examples
+//!
+//! ```
+//! # use ola::merge::{MergeMode, Merger};
+//! # use ola::DmxBuffer;
+//! let mut merger = Merger::new(MergeMode::Htp);
+//!
+//! let mut chase = DmxBuffer::new();
+//! chase.set_channel(1, 200);
+//! let mut fader = DmxBuffer::new();
+//! fader.set_channel(1, 100);
+//! fader.set_channel(2, 50);
+//!
+//! merger.update("chase", &chase, 100);
+//! merger.update("fader", &fader, 100);
+//!
+//! let merged = merger.merge().unwrap();
+//! assert_eq!(merged.channel(1), Some(200));
+//! assert_eq!(merged.channel(2), Some(50));
+//! ```
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Instant;
+
+use crate::ola::proto;
+use crate::DmxBuffer;
+
+/// How sources of the same priority are combined. This mirrors OLA's
+/// universe merge mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MergeMode {
+    /// Highest takes precedence: every channel takes the highest value of
+    /// any source.
+    #[default]
+    Htp,
+    /// Latest takes precedence: the most recently updated source wins.
+    Ltp,
+}
+
+impl From<proto::MergeMode> for MergeMode {
+    fn from(mode: proto::MergeMode) -> Self {
+        match mode {
+            proto::MergeMode::Htp => MergeMode::Htp,
+            proto::MergeMode::Ltp => MergeMode::Ltp,
+        }
+    }
+}
+
+impl From<MergeMode> for proto::MergeMode {
+    fn from(mode: MergeMode) -> Self {
+        match mode {
+            MergeMode::Htp => proto::MergeMode::Htp,
+            MergeMode::Ltp => proto::MergeMode::Ltp,
+        }
+    }
+}
+
+/// Priority of a merge source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Priority {
+    /// A single priority for the whole universe, as sent with
+    /// `send_dmx_with_priority`. Like in `olad`, 0 is the lowest priority
+    /// rather than a special value, so such a source is still merged.
+    Universe(u8),
+    /// A priority for every channel, indexed like the source's data. Here a
+    /// priority of 0 means the priority is unset, and the source doesn't
+    /// drive that channel.
+    Channel(DmxBuffer),
+}
+
+impl From<u8> for Priority {
+    fn from(priority: u8) -> Self {
+        Priority::Universe(priority)
+    }
+}
+
+impl Priority {
+    /// The priority of a slot, or `None` if the source doesn't drive it.
+    fn at(&self, slot: usize) -> Option<u8> {
+        match self {
+            Priority::Universe(priority) => Some(*priority),
+            Priority::Channel(priorities) => priorities
+                .as_slice()
+                .get(slot)
+                .copied()
+                .filter(|&priority| priority > 0),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Source {
+    data: DmxBuffer,
+    priority: Priority,
+    updated: Instant,
+    /// Position of the source's last update among all updates, breaking ties
+    /// between sources updated at the same instant.
+    sequence: u64,
+}
+
+impl Source {
+    /// Whether this source was updated after `other`.
+    fn is_later(&self, other: &Source) -> bool {
+        (self.updated, self.sequence) > (other.updated, other.sequence)
+    }
+}
+
+/// Combines DMX from several sources, keyed by `K`, into one universe.
+///
+/// If every source has a [`Priority::Universe`], the universe is merged the
+/// way `olad` does it: only the sources with the highest priority are
+/// considered, and these are combined according to the [`MergeMode`]. With
+/// [`MergeMode::Ltp`] the most recently updated of them is used as-is, while
+/// with [`MergeMode::Htp`] every channel takes the highest value, and the
+/// result is as long as the longest source.
+///
+/// If any source has a [`Priority::Channel`], each channel is merged
+/// separately instead: the sources with the highest priority for that
+/// channel are combined according to the merge mode. Sources only drive the
+/// channels they have data for, and which don't have a per-channel priority
+/// of 0.
+///
+/// With [`MergeMode::Ltp`], sources updated at the same instant are ordered
+/// by the order [`update_at`](Self::update_at) was called in, so the merge
+/// doesn't depend on how the sources happen to be stored.
+///
+/// # Examples
+///
+/// ```
+/// # use ola::merge::{MergeMode, Merger, Priority};
+/// # use ola::DmxBuffer;
+/// let mut merger = Merger::new(MergeMode::Ltp);
+///
+/// let mut backup = DmxBuffer::new();
+/// backup.set_channel(1, 255);
+/// merger.update(1, &backup, 50);
+///
+/// // only drive channel 2, leaving channel 1 to the backup
+/// let mut priorities = DmxBuffer::new();
+/// priorities.set_channel(2, 100);
+/// let mut console = DmxBuffer::new();
+/// console.set_channel(2, 10);
+/// merger.update(2, &console, Priority::Channel(priorities));
+///
+/// let merged = merger.merge().unwrap();
+/// assert_eq!(merged.channel(1), Some(255));
+/// assert_eq!(merged.channel(2), Some(10));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Merger<K> {
+    mode: MergeMode,
+    sources: HashMap<K, Source>,
+    /// Number of updates so far.
+    sequence: u64,
+}
+
+impl<K: Eq + Hash> Default for Merger<K> {
+    fn default() -> Self {
+        Self::new(MergeMode::default())
+    }
+}
+
+impl<K: Eq + Hash> Merger<K> {
+    /// Create a merger without any sources.
+    pub fn new(mode: MergeMode) -> Self {
+        Self {
+            mode,
+            sources: HashMap::new(),
+            sequence: 0,
+        }
+    }
+
+    /// How sources of the same priority are combined.
+    pub fn mode(&self) -> MergeMode {
+        self.mode
+    }
+
+    /// Change how sources of the same priority are combined.
+    pub fn set_mode(&mut self, mode: MergeMode) {
+        self.mode = mode;
+    }
+
+    /// Set the data and priority of a source, adding it if it wasn't merged
+    /// yet. The source counts as updated now.
+    pub fn update(&mut self, key: K, data: &DmxBuffer, priority: impl Into<Priority>) {
+        self.update_at(key, data, priority, Instant::now());
+    }
+
+    /// Set the data and priority of a source, as updated at `updated`. This
+    /// is used with [`MergeMode::Ltp`] to find the latest source.
+    pub fn update_at(
+        &mut self,
+        key: K,
+        data: &DmxBuffer,
+        priority: impl Into<Priority>,
+        updated: Instant,
+    ) {
+        let priority = priority.into();
+        let sequence = self.sequence;
+        self.sequence += 1;
+
+        match self.sources.get_mut(&key) {
+            Some(source) => {
+                source.data.clone_from(data);
+                source.priority = priority;
+                source.updated = updated;
+                source.sequence = sequence;
+            }
+            None => {
+                let data = data.clone();
+                self.sources.insert(
+                    key,
+                    Source {
+                        data,
+                        priority,
+                        updated,
+                        sequence,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Stop merging a source, returning whether it was present.
+    pub fn remove(&mut self, key: &K) -> bool {
+        self.sources.remove(key).is_some()
+    }
+
+    /// Stop merging every source.
+    pub fn clear(&mut self) {
+        self.sources.clear();
+    }
+
+    /// Number of sources being merged.
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// Whether there are no sources to merge.
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Merge the current data of every source, or `None` if there are no
+    /// sources.
+    pub fn merge(&self) -> Option<DmxBuffer> {
+        let per_channel = self
+            .sources
+            .values()
+            .any(|source| matches!(source.priority, Priority::Channel(_)));
+
+        if per_channel {
+            self.merge_channels()
+        } else {
+            self.merge_universe()
+        }
+    }
+
+    fn merge_universe(&self) -> Option<DmxBuffer> {
+        let top = self
+            .sources
+            .values()
+            .filter_map(|source| source.priority.at(0))
+            .max()?;
+        let mut winners = self
+            .sources
+            .values()
+            .filter(|source| source.priority.at(0) == Some(top));
+
+        match self.mode {
+            MergeMode::Ltp => winners
+                .reduce(|latest, source| {
+                    if source.is_later(latest) {
+                        source
+                    } else {
+                        latest
+                    }
+                })
+                .map(|source| source.data.clone()),
+            MergeMode::Htp => {
+                let first = winners.next()?;
+                let mut merged = first.data.clone();
+                for source in winners {
+                    if source.data.slots() > merged.slots() {
+                        merged.set_slots(source.data.slots());
+                    }
+                    for (value, other) in merged.iter_mut().zip(source.data.iter()) {
+                        *value = (*value).max(*other);
+                    }
+                }
+                Some(merged)
+            }
+        }
+    }
+
+    fn merge_channels(&self) -> Option<DmxBuffer> {
+        let slots = self
+            .sources
+            .values()
+            .map(|source| source.data.slots())
+            .max()?;
+        let mut merged = DmxBuffer::with_slots(slots);
+
+        for (slot, value) in merged.iter_mut().enumerate() {
+            let mut top = 0;
+            let mut winner: Option<&Source> = None;
+            for source in self.sources.values() {
+                let Some(&candidate) = source.data.as_slice().get(slot) else {
+                    continue;
+                };
+                let Some(priority) = source.priority.at(slot) else {
+                    continue;
+                };
+
+                let wins = match winner {
+                    None => true,
+                    Some(_) if priority > top => true,
+                    Some(_) if priority < top => false,
+                    Some(current) => match self.mode {
+                        MergeMode::Htp => candidate > current.data[slot],
+                        MergeMode::Ltp => source.is_later(current),
+                    },
+                };
+                if wins {
+                    top = priority;
+                    winner = Some(source);
+                }
+            }
+
+            if let Some(source) = winner {
+                *value = source.data[slot];
+            }
+        }
+
+        Some(merged)
+    }
+}
@@ -21,6 +21,7 @@ use crate::{PROTOCOL_VERSION, SIZE_MASK, VERSION_MASK};
 use proto::rpc::RpcMessage;
 
 use bytes::{BufMut, BytesMut};
+use prost::encoding::{self, WireType};
 use prost::Message;
 
 /// Methods that can be sent over an RPC channel.
@@ -31,30 +32,103 @@ pub trait RpcCall: Sized {
     fn to_message(&self, id: u32) -> proto::rpc::RpcMessage;
 }
 
+/// Byte order of the RPC message header.
+///
+/// `olad` uses host endianness for the RPC header (see
+/// [OLA issue 1795](https://github.com/OpenLightingProject/ola/issues/1795)),
+/// so talking to an `olad` running on an architecture of different
+/// endianness requires picking the byte order explicitly. It can't be
+/// detected from `olad`'s messages, as the client always speaks first.
+///
+/// # Examples
+///
+/// ```
+/// # use ola::ola::{decode_header_as, encode_header_as, Endianness};
+/// let header = encode_header_as(1, 42, Endianness::Big);
+/// assert_eq!(header, [0x10, 0x00, 0x00, 0x2a]);
+/// assert_eq!(decode_header_as(header, Endianness::Big), (1, 42));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Endianness {
+    /// Use the byte order of this machine.
+    #[default]
+    Native,
+    /// Use little-endian headers.
+    Little,
+    /// Use big-endian headers.
+    Big,
+}
+
 /// Encode an RPC message header.
 pub fn encode_header(version: u32, size: usize) -> [u8; 4] {
+    encode_header_as(version, size, Endianness::Native)
+}
+
+/// Encode an RPC message header with the given byte order.
+pub fn encode_header_as(version: u32, size: usize, endianness: Endianness) -> [u8; 4] {
     let mut header = size as u32 & SIZE_MASK;
     header |= (version << 28) & VERSION_MASK;
 
-    // `olad` uses host endianness for decoding the RPC header. See:
-    // https://github.com/OpenLightingProject/ola/issues/1795
-    header.to_ne_bytes()
+    match endianness {
+        Endianness::Little => header.to_le_bytes(),
+        Endianness::Big => header.to_be_bytes(),
+        // `olad` uses host endianness for decoding the RPC header. See:
+        // https://github.com/OpenLightingProject/ola/issues/1795
+        Endianness::Native => header.to_ne_bytes(),
+    }
 }
 
 /// Decodes an RPC message header to get it's protocol version and message
 /// size.
 pub fn decode_header(header: [u8; 4]) -> (u32, usize) {
-    let header = u32::from_ne_bytes(header);
+    decode_header_as(header, Endianness::Native)
+}
+
+/// Decodes an RPC message header with the given byte order.
+pub fn decode_header_as(header: [u8; 4], endianness: Endianness) -> (u32, usize) {
+    let header = match endianness {
+        Endianness::Little => u32::from_le_bytes(header),
+        Endianness::Big => u32::from_be_bytes(header),
+        Endianness::Native => u32::from_ne_bytes(header),
+    };
     let version = header >> 28;
     let size = (header & SIZE_MASK) as usize;
 
     (version, size)
 }
 
+/// Encoded length of a length-delimited field holding `len` bytes.
+fn bytes_len(tag: u32, len: usize) -> usize {
+    encoding::key_len(tag) + encoding::encoded_len_varint(len as u64) + len
+}
+
+/// Encode a length-delimited field.
+fn put_bytes(tag: u32, value: &[u8], dst: &mut BytesMut) {
+    encoding::encode_key(tag, WireType::LengthDelimited, dst);
+    encoding::encode_varint(value.len() as u64, dst);
+    dst.put_slice(value);
+}
+
+/// Default limit on the size of a received message, in bytes.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 1024 * 1024;
+
 /// Context for encoding and decoding RPC messages in a session.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug)]
 pub struct RpcContext {
     sequence_number: u32,
+    max_frame_size: usize,
+    endianness: Endianness,
+}
+
+impl Default for RpcContext {
+    fn default() -> Self {
+        Self {
+            sequence_number: 0,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            endianness: Endianness::default(),
+        }
+    }
 }
 
 impl RpcContext {
@@ -63,7 +137,32 @@ impl RpcContext {
         Default::default()
     }
 
-    fn next_sequence(&mut self) -> u32 {
+    /// The largest message (excluding its header) accepted by
+    /// [`decode_frame`](Self::decode_frame).
+    pub fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+
+    /// Set the largest message (excluding its header) accepted by
+    /// [`decode_frame`](Self::decode_frame). Larger messages fail to decode
+    /// with [`MessageDecodeErrorKind::FrameTooLarge`] before anything is
+    /// buffered for them. Defaults to [`DEFAULT_MAX_FRAME_SIZE`].
+    pub fn set_max_frame_size(&mut self, size: usize) {
+        self.max_frame_size = size;
+    }
+
+    /// The byte order used for message headers.
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// Set the byte order used for message headers. Defaults to
+    /// [`Endianness::Native`], matching `olad`.
+    pub fn set_endianness(&mut self, endianness: Endianness) {
+        self.endianness = endianness;
+    }
+
+    pub(crate) fn next_sequence(&mut self) -> u32 {
         let number = self.sequence_number;
         self.sequence_number += 1;
 
@@ -71,9 +170,9 @@ impl RpcContext {
     }
 
     /// Encode an RPC call as a new message.
-    pub fn encode(
+    pub fn encode<C: RpcCall>(
         &mut self,
-        item: proto::OlaServerServiceCall,
+        item: C,
         dst: &mut BytesMut,
     ) -> Result<(), MessageEncodeError> {
         let message = item.to_message(self.next_sequence());
@@ -89,7 +188,7 @@ impl RpcContext {
     ) -> Result<(), MessageEncodeError> {
         let size = message.encoded_len();
 
-        dst.put_slice(&encode_header(PROTOCOL_VERSION, size));
+        dst.put_slice(&encode_header_as(PROTOCOL_VERSION, size, self.endianness));
         message.encode(dst).map_err(|e| MessageEncodeError {
             kind: MessageEncodeErrorKind::Capacity(e),
         })?;
@@ -97,6 +196,122 @@ impl RpcContext {
         Ok(())
     }
 
+    /// Encode a `StreamDmxData` call straight from a slice of DMX data,
+    /// returning its sequence number.
+    ///
+    /// This produces the same bytes as encoding
+    /// [`OlaServerServiceCall::StreamDmxData`](proto::OlaServerServiceCall::StreamDmxData)
+    /// with [`encode`](Self::encode), but without building the intermediate
+    /// messages. When `dst` is reused between frames, streaming DMX doesn't
+    /// allocate at all.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bytes::BytesMut;
+    /// # use ola::ola::proto::{DmxData, OlaServerServiceCall};
+    /// # use ola::ola::RpcContext;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let data = [255; 512];
+    ///
+    /// let mut direct = BytesMut::new();
+    /// RpcContext::new().encode_stream_dmx(1, &data, 100, &mut direct);
+    ///
+    /// let mut message = BytesMut::new();
+    /// let call = OlaServerServiceCall::StreamDmxData(DmxData {
+    ///     universe: 1,
+    ///     data: data.to_vec(),
+    ///     priority: Some(100),
+    /// });
+    /// RpcContext::new().encode(call, &mut message)?;
+    ///
+    /// assert_eq!(direct, message);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn encode_stream_dmx(
+        &mut self,
+        universe: u32,
+        data: &[u8],
+        priority: u8,
+        dst: &mut BytesMut,
+    ) -> u32 {
+        const NAME: &str = "StreamDmxData";
+
+        let id = self.next_sequence();
+        let universe = universe as i32;
+        let priority = priority as i32;
+        let r#type = proto::rpc::Type::StreamRequest as i32;
+
+        // DmxData, encoded as the call's buffer
+        let dmx_len = encoding::int32::encoded_len(1, &universe)
+            + bytes_len(2, data.len())
+            + encoding::int32::encoded_len(3, &priority);
+        // RpcMessage
+        let size = encoding::int32::encoded_len(1, &r#type)
+            + encoding::uint32::encoded_len(2, &id)
+            + bytes_len(3, NAME.len())
+            + bytes_len(4, dmx_len);
+
+        dst.reserve(4 + size);
+        dst.put_slice(&encode_header_as(PROTOCOL_VERSION, size, self.endianness));
+        encoding::int32::encode(1, &r#type, dst);
+        encoding::uint32::encode(2, &id, dst);
+        put_bytes(3, NAME.as_bytes(), dst);
+        encoding::encode_key(4, WireType::LengthDelimited, dst);
+        encoding::encode_varint(dmx_len as u64, dst);
+        encoding::int32::encode(1, &universe, dst);
+        put_bytes(2, data, dst);
+        encoding::int32::encode(3, &priority, dst);
+
+        id
+    }
+
+    /// Split the next complete RPC message off the front of `src`. Returns
+    /// `None` if `src` doesn't hold an entire message yet, in which case more
+    /// data needs to be read into it first.
+    ///
+    /// Messages with a protocol version other than the one spoken by this
+    /// crate, or larger than the [maximum frame size](Self::max_frame_size),
+    /// are rejected. Either leaves the stream in an unknown state, so the
+    /// connection should be dropped.
+    pub fn decode_frame(
+        &mut self,
+        src: &mut BytesMut,
+    ) -> Result<Option<RpcMessage>, MessageDecodeError> {
+        if src.len() < 4 {
+            // must load more to read header
+            return Ok(None);
+        }
+
+        let mut header = [0; 4];
+        header.copy_from_slice(&src[0..4]);
+        let (version, size) = decode_header_as(header, self.endianness);
+        if version != PROTOCOL_VERSION {
+            return Err(MessageDecodeError {
+                kind: MessageDecodeErrorKind::UnsupportedVersion(version),
+            });
+        }
+        if size > self.max_frame_size {
+            return Err(MessageDecodeError {
+                kind: MessageDecodeErrorKind::FrameTooLarge(size),
+            });
+        }
+
+        if src.len() < 4 + size {
+            // must load more to read entire message
+            src.reserve(4 + size - src.len());
+            return Ok(None);
+        }
+
+        let frame = src.split_to(4 + size);
+        let message = RpcMessage::decode(&frame[4..]).map_err(|e| MessageDecodeError {
+            kind: MessageDecodeErrorKind::Invalid(e),
+        })?;
+
+        Ok(Some(message))
+    }
+
     /// Encode a buffer containing a message as an RPC call.
     pub fn decode(buf: &[u8]) -> Result<(u32, proto::OlaClientServiceCall), MessageDecodeError> {
         let message = RpcMessage::decode(buf).map_err(|e| MessageDecodeError {
@@ -143,6 +358,18 @@ pub struct MessageDecodeError {
     pub kind: MessageDecodeErrorKind,
 }
 
+impl MessageDecodeError {
+    /// Whether the error came from a frame header, after which the stream
+    /// can't be read any further. See [`RpcContext::decode_frame`].
+    pub(crate) fn is_framing(&self) -> bool {
+        matches!(
+            self.kind,
+            MessageDecodeErrorKind::UnsupportedVersion(_)
+                | MessageDecodeErrorKind::FrameTooLarge(_)
+        )
+    }
+}
+
 impl Display for MessageDecodeError {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(f, "failed to decode API message")
@@ -154,6 +381,8 @@ impl Error for MessageDecodeError {
         match &self.kind {
             MessageDecodeErrorKind::Invalid(e) => Some(e),
             MessageDecodeErrorKind::Unrecognised => None,
+            MessageDecodeErrorKind::UnsupportedVersion(_) => None,
+            MessageDecodeErrorKind::FrameTooLarge(_) => None,
         }
     }
 }
@@ -166,4 +395,9 @@ pub enum MessageDecodeErrorKind {
     Invalid(prost::DecodeError),
     /// Recieved message was of an unknown method or type.
     Unrecognised,
+    /// Received message header has an unsupported protocol version.
+    UnsupportedVersion(u32),
+    /// Received message header announces a message larger than the maximum
+    /// frame size.
+    FrameTooLarge(usize),
 }
@@ -0,0 +1,417 @@
+//! Management of `olad` processes.
+//!
+//! By default, an auto-started `olad` is daemonized and left running after the
+//! client goes away. [`Config::launch`] instead keeps `olad` as a child of the
+//! current process, returning an [`OladHandle`] which can be used to stop it
+//! again.
+//!
+//! If `olad` should be kept running unattended, [`Config::supervise`] also
+//! watches the child and restarts it whenever it exits unexpectedly.
+//!
+//! [`Config::launch`]: crate::config::Config::launch
+//! [`Config::supervise`]: crate::config::Config::supervise
+
+use std::fmt::{self, Debug, Formatter};
+use std::io::{self, BufRead, BufReader, Read};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, sleep, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::config::{Config, SpawnOladError, OLA_SPAWN_DELAY};
+
+const OLAD_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+type LogFn = dyn Fn(OladOutput, &str) + Send + Sync;
+
+/// Which output stream of `olad` a logged line was read from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OladOutput {
+    /// Standard output.
+    Stdout,
+    /// Standard error. `olad` writes its log here unless `--syslog` is set.
+    Stderr,
+}
+
+/// Callback receiving the output of a managed `olad`, one line at a time.
+///
+/// The callback is run on a background thread for each of `olad`'s output
+/// streams.
+///
+/// # Examples
+///
+/// ```
+/// # use ola::olad::OladLogger;
+/// let logger = OladLogger::new(|output, line| eprintln!("olad ({output:?}): {line}"));
+/// ```
+#[derive(Clone)]
+pub struct OladLogger(Arc<LogFn>);
+
+impl OladLogger {
+    /// Wrap a function as an `olad` logger.
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(OladOutput, &str) + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    fn capture<R: Read + Send + 'static>(&self, output: OladOutput, stream: R) -> JoinHandle<()> {
+        let logger = self.clone();
+
+        thread::spawn(move || {
+            for line in BufReader::new(stream).lines() {
+                match line {
+                    Ok(line) => (logger.0)(output, &line),
+                    Err(_) => break,
+                }
+            }
+        })
+    }
+}
+
+impl Debug for OladLogger {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OladLogger").finish_non_exhaustive()
+    }
+}
+
+/// A handle to an `olad` process owned by this process.
+///
+/// If the handle was created with `kill_on_drop` set (see
+/// [`Config::kill_on_drop`]), `olad` is killed when the handle is dropped.
+/// Otherwise it keeps running, like a dropped [`Child`].
+///
+/// [`Config::kill_on_drop`]: crate::config::Config::kill_on_drop
+#[derive(Debug)]
+pub struct OladHandle {
+    child: Child,
+    kill_on_drop: bool,
+    loggers: Vec<JoinHandle<()>>,
+}
+
+impl OladHandle {
+    pub(crate) fn spawn(
+        mut command: Command,
+        logger: Option<&OladLogger>,
+        kill_on_drop: bool,
+    ) -> io::Result<Self> {
+        command.stdin(Stdio::null());
+        if logger.is_some() {
+            command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        }
+
+        let mut child = command.spawn()?;
+        let mut loggers = Vec::new();
+        if let Some(logger) = logger {
+            if let Some(stdout) = child.stdout.take() {
+                loggers.push(logger.capture(OladOutput::Stdout, stdout));
+            }
+            if let Some(stderr) = child.stderr.take() {
+                loggers.push(logger.capture(OladOutput::Stderr, stderr));
+            }
+        }
+
+        Ok(Self {
+            child,
+            kill_on_drop,
+            loggers,
+        })
+    }
+
+    /// The OS-assigned process identifier of `olad`.
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Check whether `olad` has exited without blocking. Returns the exit
+    /// status if it has.
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        self.child.try_wait()
+    }
+
+    /// Wait for `olad` to exit. Any output still being captured is passed to
+    /// the logger before this returns.
+    pub fn wait(&mut self) -> io::Result<ExitStatus> {
+        let status = self.child.wait()?;
+        for logger in self.loggers.drain(..) {
+            let _ = logger.join();
+        }
+
+        Ok(status)
+    }
+
+    /// Wait up to `timeout` for `olad` to exit. Returns `None` if it is still
+    /// running once `timeout` has elapsed.
+    pub fn wait_timeout(&mut self, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.child.try_wait()?.is_some() {
+                return self.wait().map(Some);
+            } else if Instant::now() >= deadline {
+                return Ok(None);
+            }
+            sleep(OLAD_POLL_INTERVAL);
+        }
+    }
+
+    /// Forcibly kill `olad` and wait for it to exit.
+    pub fn kill(&mut self) -> io::Result<ExitStatus> {
+        self.child.kill()?;
+
+        self.wait()
+    }
+
+    /// Ask `olad` to shut down, killing it if it hasn't exited within
+    /// `timeout`.
+    ///
+    /// On Unix, `olad` is sent `SIGTERM` which lets it save its configuration
+    /// before exiting. On other platforms, this is equivalent to
+    /// [`kill`](Self::kill).
+    pub fn shutdown(&mut self, timeout: Duration) -> io::Result<ExitStatus> {
+        if self.child.try_wait()?.is_some() {
+            return self.wait();
+        }
+
+        #[cfg(unix)]
+        {
+            // SAFETY: `kill` has no memory safety requirements. The child
+            // hasn't been reaped yet, so its pid can't have been reused.
+            if unsafe { libc::kill(self.child.id() as libc::pid_t, libc::SIGTERM) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if let Some(status) = self.wait_timeout(timeout)? {
+                return Ok(status);
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = timeout;
+
+        self.kill()
+    }
+}
+
+impl Drop for OladHandle {
+    fn drop(&mut self) {
+        if self.kill_on_drop && matches!(self.child.try_wait(), Ok(None)) {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+/// Events reported by a [`Supervisor`].
+#[derive(Clone, Debug)]
+pub enum SupervisorEvent {
+    /// `olad` exited unexpectedly with the given status. A restart will be
+    /// attempted after the current backoff delay.
+    Exited(ExitStatus),
+    /// `olad` was restarted and should now be accepting connections. Clients
+    /// connected to the previous instance need to reconnect.
+    Restarted,
+    /// Restarting `olad` failed. Another attempt will be made after the
+    /// current backoff delay.
+    SpawnFailed(Arc<SpawnOladError>),
+}
+
+#[derive(Debug)]
+struct SupervisorShared {
+    config: Config,
+    olad: Mutex<Option<OladHandle>>,
+    stopped: Mutex<bool>,
+    stop: Condvar,
+    subscribers: Mutex<Vec<Sender<SupervisorEvent>>>,
+    generation: AtomicU64,
+}
+
+impl SupervisorShared {
+    fn olad(&self) -> MutexGuard<'_, Option<OladHandle>> {
+        self.olad.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn notify(&self, event: SupervisorEvent) {
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+
+    /// Sleep for `duration`, returning early with `true` if the supervisor
+    /// was stopped in the meantime.
+    fn sleep(&self, duration: Duration) -> bool {
+        let stopped = self.stopped.lock().unwrap_or_else(|e| e.into_inner());
+        let (stopped, _) = self
+            .stop
+            .wait_timeout_while(stopped, duration, |stopped| !*stopped)
+            .unwrap_or_else(|e| e.into_inner());
+
+        *stopped
+    }
+
+    fn run(&self) {
+        let backoff = self.config.restart_backoff;
+        let mut attempt = 0;
+        let mut started = Instant::now();
+
+        loop {
+            if self.sleep(OLAD_POLL_INTERVAL) {
+                return;
+            }
+
+            // forget the exited process straight away, so its (possibly
+            // reused) pid isn't reported while waiting to restart it
+            let exited = {
+                let mut olad = self.olad();
+                match olad.as_mut().map(OladHandle::try_wait) {
+                    Some(Ok(Some(status))) => olad.take().map(|olad| (olad, status)),
+                    _ => None,
+                }
+            };
+            let Some((mut olad, status)) = exited else {
+                continue;
+            };
+            // collect any remaining output
+            let _ = olad.wait();
+            self.notify(SupervisorEvent::Exited(status));
+
+            // Only back off further if olad keeps crashing soon after being
+            // started.
+            if started.elapsed() > backoff.max {
+                attempt = 0;
+            }
+            loop {
+                if self.sleep(backoff.delay(attempt)) {
+                    return;
+                }
+                attempt = attempt.saturating_add(1);
+
+                let olad = OladHandle::spawn(
+                    self.config.child_command(),
+                    self.config.olad_log.as_ref(),
+                    self.config.kill_on_drop,
+                );
+                match olad {
+                    Ok(olad) => {
+                        *self.olad() = Some(olad);
+                        started = Instant::now();
+                        break;
+                    }
+                    Err(e) => {
+                        self.notify(SupervisorEvent::SpawnFailed(Arc::new(SpawnOladError(e))))
+                    }
+                }
+            }
+
+            if self.sleep(OLA_SPAWN_DELAY) {
+                return;
+            }
+            self.generation.fetch_add(1, Ordering::SeqCst);
+            self.notify(SupervisorEvent::Restarted);
+        }
+    }
+}
+
+/// Keeps a managed `olad` running by restarting it when it exits.
+///
+/// A supervisor is started with [`Config::supervise`]. It polls its `olad`
+/// child from a background thread and, if it exits unexpectedly, starts a new
+/// one with the same configuration. Consecutive failures are retried with
+/// exponential backoff as configured by [`Config::restart_backoff`].
+///
+/// Clients connected to a crashed `olad` don't notice the restart on their
+/// own. Use [`subscribe`](Self::subscribe) or
+/// [`generation`](Self::generation) to find out when to reconnect.
+///
+/// Dropping the supervisor stops it. `olad` is then treated the same as a
+/// dropped [`OladHandle`].
+///
+/// [`Config::supervise`]: crate::config::Config::supervise
+/// [`Config::restart_backoff`]: crate::config::Config::restart_backoff
+#[derive(Debug)]
+pub struct Supervisor {
+    shared: Arc<SupervisorShared>,
+    watcher: Option<JoinHandle<()>>,
+}
+
+impl Supervisor {
+    pub(crate) fn start(config: Config, olad: OladHandle) -> Self {
+        let shared = Arc::new(SupervisorShared {
+            config,
+            olad: Mutex::new(Some(olad)),
+            stopped: Mutex::new(false),
+            stop: Condvar::new(),
+            subscribers: Mutex::new(Vec::new()),
+            generation: AtomicU64::new(0),
+        });
+
+        let watcher = {
+            let shared = shared.clone();
+            thread::spawn(move || shared.run())
+        };
+
+        Self {
+            shared,
+            watcher: Some(watcher),
+        }
+    }
+
+    /// Receive events about `olad` exiting and being restarted. Each
+    /// subscriber receives every event from the moment it subscribed.
+    pub fn subscribe(&self) -> Receiver<SupervisorEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.shared
+            .subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(sender);
+
+        receiver
+    }
+
+    /// The number of times `olad` has been restarted. A client can record
+    /// this when connecting and reconnect once it has changed.
+    pub fn generation(&self) -> u64 {
+        self.shared.generation.load(Ordering::SeqCst)
+    }
+
+    /// The process identifier of the currently running `olad`, if any. This
+    /// is `None` between `olad` exiting and being restarted.
+    pub fn id(&self) -> Option<u32> {
+        self.shared.olad().as_ref().map(OladHandle::id)
+    }
+
+    fn stop(&mut self) {
+        *self
+            .shared
+            .stopped
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = true;
+        self.shared.stop.notify_all();
+        if let Some(watcher) = self.watcher.take() {
+            let _ = watcher.join();
+        }
+    }
+
+    /// Stop supervising and shut `olad` down. See [`OladHandle::shutdown`].
+    pub fn shutdown(mut self, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+        self.stop();
+
+        let olad = self.shared.olad().take();
+        olad.map(|mut olad| olad.shutdown(timeout)).transpose()
+    }
+
+    /// Stop supervising and return the handle of the currently running
+    /// `olad`, leaving it running.
+    pub fn into_inner(mut self) -> Option<OladHandle> {
+        self.stop();
+
+        let olad = self.shared.olad().take();
+        olad
+    }
+}
+
+impl Drop for Supervisor {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
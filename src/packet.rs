@@ -0,0 +1,142 @@
+//! DMX packets with a start code.
+//!
+//! Every DMX512-A packet begins with a start code slot describing what the
+//! rest of the packet holds. Level data uses the null start code (0x00),
+//! which is what [`DmxBuffer`] represents, while alternate start codes carry
+//! other kinds of data such as text or system information packets. A
+//! [`Packet`] pairs a start code with its payload.
+//!
+//! OLA only transports null start code data: its `DmxData` message has no
+//! field for the start code, and `olad` treats everything it receives as
+//! level data. The clients therefore only send [`DmxBuffer`]s, taken from a
+//! packet with [`Packet::data`]. [`Session::send_packet`](crate::session::Session::send_packet)
+//! checks the start code first, failing with
+//! [`CallErrorKind::UnsupportedStartCode`](crate::client::CallErrorKind::UnsupportedStartCode)
+//! instead of silently sending other data as levels. Received universes are
+//! always null start code packets, and can be converted with
+//! [`Packet::from`]. Packets can also be converted to and from their wire
+//! layout, for use with other transports.
+//!
+//! # Examples
+//!
+//! ```
+//! # use ola::packet::{Packet, StartCode};
+//! # use ola::DmxBuffer;
+//! let text = Packet::new(StartCode::TEXT, DmxBuffer::try_from(&b"\x01\x10hello"[..]).unwrap());
+//! assert_eq!(text.to_bytes()[..3], [0x17, 0x01, 0x10]);
+//!
+//! let levels = Packet::from(DmxBuffer::new());
+//! assert!(levels.start_code().is_null());
+//! ```
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::{DmxBuffer, TryFromBufferError};
+
+/// The first slot of a DMX packet, identifying what the packet holds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StartCode(pub u8);
+
+impl StartCode {
+    /// Dimmer and other level data (0x00).
+    pub const NULL: StartCode = StartCode(0x00);
+    /// ASCII text packet (0x17).
+    pub const TEXT: StartCode = StartCode(0x17);
+    /// Test packet (0x55).
+    pub const TEST: StartCode = StartCode(0x55);
+    /// UTF-8 text packet (0x90).
+    pub const UTF8_TEXT: StartCode = StartCode(0x90);
+    /// Manufacturer ID packet (0x91).
+    pub const MANUFACTURER: StartCode = StartCode(0x91);
+    /// Remote Device Management (0xCC).
+    pub const RDM: StartCode = StartCode(0xcc);
+    /// System Information Packet (0xCF).
+    pub const SYSTEM_INFORMATION: StartCode = StartCode(0xcf);
+
+    /// Whether this is the null start code, used for level data.
+    pub fn is_null(self) -> bool {
+        self == Self::NULL
+    }
+}
+
+impl Display for StartCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#04x}", self.0)
+    }
+}
+
+/// A DMX packet, made up of a start code and up to 512 slots of payload.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Packet {
+    start_code: StartCode,
+    data: DmxBuffer,
+}
+
+impl Packet {
+    /// Construct a packet from a start code and its payload.
+    pub fn new(start_code: StartCode, data: DmxBuffer) -> Self {
+        Self { start_code, data }
+    }
+
+    /// The start code of the packet.
+    pub fn start_code(&self) -> StartCode {
+        self.start_code
+    }
+
+    /// The payload of the packet, without the start code.
+    pub fn data(&self) -> &DmxBuffer {
+        &self.data
+    }
+
+    /// The payload of the packet as mutable.
+    pub fn data_mut(&mut self) -> &mut DmxBuffer {
+        &mut self.data
+    }
+
+    /// Take the payload out of the packet.
+    pub fn into_data(self) -> DmxBuffer {
+        self.data
+    }
+
+    /// The packet as sent on the wire: the start code followed by the
+    /// payload.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(1 + self.data.slots());
+        bytes.push(self.start_code.0);
+        bytes.extend_from_slice(self.data.as_slice());
+
+        bytes
+    }
+}
+
+impl From<DmxBuffer> for Packet {
+    /// Construct a null start code packet holding level data.
+    fn from(data: DmxBuffer) -> Self {
+        Self::new(StartCode::NULL, data)
+    }
+}
+
+impl TryFrom<&[u8]> for Packet {
+    type Error = TryFromBufferError;
+
+    /// Parse a packet from its wire layout, a start code followed by 1 to
+    /// 512 slots.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ola::packet::{Packet, StartCode};
+    /// let packet = Packet::try_from(&[0xcf, 1, 2, 3][..]).unwrap();
+    /// assert_eq!(packet.start_code(), StartCode::SYSTEM_INFORMATION);
+    /// assert_eq!(**packet.data(), [1, 2, 3]);
+    ///
+    /// assert!(Packet::try_from(&[0x00][..]).is_err());
+    /// ```
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let (&start_code, data) = bytes.split_first().ok_or(TryFromBufferError(()))?;
+
+        Ok(Self::new(StartCode(start_code), DmxBuffer::try_from(data)?))
+    }
+}
@@ -0,0 +1,434 @@
+//! Runtime-agnostic protocol state machine.
+//!
+//! [`Session`] implements the client side of the OLA RPC protocol without
+//! performing any I/O itself. Bytes read from `olad` are fed in with
+//! [`Session::receive`], which decodes them into [`Event`]s, and anything
+//! that needs to be sent back is collected in a transmit buffer for the
+//! caller to write out. All of the clients in [`client`](crate::client) are
+//! built on top of it, and it can be used to drive a connection to OLA from
+//! any other event loop.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use ola::session::{Event, Session};
+//! # use std::io::{Read, Write};
+//! # use std::net::TcpStream;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut stream = TcpStream::connect("127.0.0.1:9010")?;
+//! let mut session = Session::new();
+//!
+//! session.register_universe(1)?;
+//! loop {
+//!     stream.write_all(session.transmit())?;
+//!     session.advance_transmit(session.transmit().len());
+//!
+//!     while let Some(event) = session.poll_event() {
+//!         if let Event::Dmx(data) = event {
+//!             println!("universe {} updated", data.universe);
+//!         }
+//!     }
+//!
+//!     let mut chunk = [0; 4096];
+//!     let read = stream.read(&mut chunk)?;
+//!     session.receive(&chunk[..read])?;
+//! }
+//! # }
+//! ```
+
+use std::collections::{HashSet, VecDeque};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use crate::client::{CallError, CallErrorKind};
+use crate::ola::proto::{
+    rpc::{RpcMessage, Type},
+    Ack, DmxData, OlaClientServiceCall, OlaServerServiceCall, RegisterAction, RegisterDmxRequest,
+};
+use crate::ola::{Endianness, MessageDecodeErrorKind, RpcCall, RpcContext};
+use crate::packet::Packet;
+use crate::DmxBuffer;
+
+use bytes::{Buf, BytesMut};
+use prost::Message;
+
+/// Something that happened on a [`Session`] which the caller may need to act
+/// on.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Event {
+    /// OLA sent new data for a universe the client registered for. The
+    /// acknowledgement has already been queued for transmission.
+    Dmx(DmxData),
+    /// OLA answered a call made with [`Session::call`].
+    Response {
+        /// Sequence number of the call being answered.
+        id: u32,
+        /// Encoded response message.
+        buffer: Vec<u8>,
+    },
+    /// OLA refused to answer a call made with [`Session::call`].
+    Rejected {
+        /// Sequence number of the call being rejected.
+        id: u32,
+        /// Why the call was rejected.
+        reason: Rejection,
+    },
+    /// OLA announced that it is closing the connection.
+    Disconnected,
+}
+
+/// Reason OLA gave for not answering a call.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Rejection {
+    /// OLA cancelled the call.
+    Cancelled,
+    /// OLA failed to handle the call, with its error message.
+    Failed(String),
+    /// OLA doesn't implement the called method.
+    NotImplemented,
+}
+
+impl Display for Rejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::Cancelled => write!(f, "call was cancelled by OLA"),
+            Rejection::Failed(reason) => write!(f, "call failed: {reason}"),
+            Rejection::NotImplemented => write!(f, "method is not implemented by OLA"),
+        }
+    }
+}
+
+impl Error for Rejection {}
+
+/// Protocol state of a single connection with OLA.
+///
+/// A session tracks the sequence numbers of outgoing messages, buffers
+/// partially received frames, remembers which calls are still waiting for a
+/// response, and acknowledges DMX updates from OLA.
+#[derive(Clone, Debug, Default)]
+pub struct Session {
+    ctx: RpcContext,
+    received: BytesMut,
+    transmit: BytesMut,
+    pending: HashSet<u32>,
+    events: VecDeque<Event>,
+}
+
+impl Session {
+    /// Build the state for a new connection.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the largest message accepted from OLA, in bytes. Larger messages
+    /// fail to decode with [`MessageDecodeErrorKind::FrameTooLarge`].
+    pub fn set_max_frame_size(&mut self, size: usize) {
+        self.ctx.set_max_frame_size(size);
+    }
+
+    /// Set the byte order of message headers. See
+    /// [`RpcContext::set_endianness`].
+    pub fn set_endianness(&mut self, endianness: Endianness) {
+        self.ctx.set_endianness(endianness);
+    }
+
+    /// Feed bytes received from OLA into the session. Every complete message
+    /// is processed, after which new events may be available from
+    /// [`poll_event`](Self::poll_event) and replies may have been queued for
+    /// transmission.
+    pub fn receive(&mut self, data: &[u8]) -> Result<(), CallError> {
+        self.received.extend_from_slice(data);
+
+        self.process()
+    }
+
+    /// The buffer received bytes are collected in. This allows reading from
+    /// a stream directly into the session, which must be followed by a call
+    /// to [`process`](Self::process).
+    pub fn receive_buf(&mut self) -> &mut BytesMut {
+        &mut self.received
+    }
+
+    /// Process every complete message in the receive buffer. See
+    /// [`receive`](Self::receive).
+    ///
+    /// A message which can't be handled doesn't hold up the ones after it:
+    /// they are still processed, and the first error is returned once the
+    /// buffer has been drained. Only a frame header which can't be read (see
+    /// [`RpcContext::decode_frame`]) stops processing right away, as the
+    /// remaining bytes can't be made sense of.
+    ///
+    /// ```
+    /// # use bytes::BytesMut;
+    /// # use ola::ola::proto::{DmxData, OlaClientServiceCall};
+    /// # use ola::ola::{encode_header, RpcContext};
+    /// # use ola::session::{Event, Session};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// // a frame which isn't a valid message, followed by a DMX update
+    /// let mut received = BytesMut::new();
+    /// received.extend_from_slice(&encode_header(1, 1));
+    /// received.extend_from_slice(&[0xff]);
+    /// let update = DmxData {
+    ///     universe: 1,
+    ///     data: vec![0; 512],
+    ///     priority: None,
+    /// };
+    /// RpcContext::new().encode(OlaClientServiceCall::UpdateDmxData(update), &mut received)?;
+    ///
+    /// let mut session = Session::new();
+    /// assert!(session.receive(&received).is_err());
+    /// assert!(matches!(session.poll_event(), Some(Event::Dmx(_))));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn process(&mut self) -> Result<(), CallError> {
+        let mut result = Ok(());
+        loop {
+            let message = match self.ctx.decode_frame(&mut self.received) {
+                Ok(Some(message)) => message,
+                Ok(None) => return result,
+                Err(e) => {
+                    let fatal = e.is_framing();
+                    let error = CallError {
+                        kind: CallErrorKind::Decode(e),
+                    };
+                    if fatal {
+                        return Err(error);
+                    }
+
+                    // the invalid frame was consumed, so carry on after it
+                    result = result.and(Err(error));
+                    continue;
+                }
+            };
+
+            if let Err(e) = self.handle(message) {
+                result = result.and(Err(e));
+            }
+        }
+    }
+
+    fn handle(&mut self, message: RpcMessage) -> Result<(), CallError> {
+        let Some(r#type) = Type::from_i32(message.r#type) else {
+            // not a message type we know how to answer
+            return Ok(());
+        };
+
+        match r#type {
+            Type::Response => {
+                if let Some(id) = message.id {
+                    if self.pending.remove(&id) {
+                        let buffer = message.buffer.unwrap_or_default();
+                        self.events.push_back(Event::Response { id, buffer });
+                    }
+                }
+            }
+            Type::ResponseCancel => self.reject(message.id, Rejection::Cancelled),
+            Type::ResponseFailed => {
+                let reason = String::from_utf8_lossy(&message.buffer.unwrap_or_default()).into();
+                self.reject(message.id, Rejection::Failed(reason));
+            }
+            Type::ResponseNotImplemented => self.reject(message.id, Rejection::NotImplemented),
+            Type::Disconnect => self.events.push_back(Event::Disconnected),
+            Type::DescriptorRequest => {
+                // service descriptors aren't available at runtime
+                if let Some(id) = message.id {
+                    self.reply(Type::ResponseNotImplemented, id, None)?;
+                }
+            }
+            // descriptors are never requested, and requests are answered as
+            // soon as they arrive so there is nothing left to cancel
+            Type::DescriptorResponse | Type::RequestCancel => {}
+            Type::Request | Type::StreamRequest => self.handle_request(message)?,
+        }
+
+        Ok(())
+    }
+
+    fn handle_request(&mut self, message: RpcMessage) -> Result<(), CallError> {
+        // streamed requests must never be answered
+        let reply_id = match message.r#type() {
+            Type::Request => message.id,
+            _ => None,
+        };
+
+        match OlaClientServiceCall::from_message(message) {
+            Ok((id, OlaClientServiceCall::UpdateDmxData(data))) => {
+                self.respond(id, "Ack", Ack {}.encode_to_vec())?;
+                self.events.push_back(Event::Dmx(data));
+            }
+            Err(e) => {
+                if let Some(id) = reply_id {
+                    match e.kind {
+                        MessageDecodeErrorKind::Invalid(e) => {
+                            let reason = e.to_string().into_bytes();
+                            self.reply(Type::ResponseFailed, id, Some(reason))?;
+                        }
+                        _ => self.reply(Type::ResponseNotImplemented, id, None)?,
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fail a pending call which OLA refused to answer.
+    fn reject(&mut self, id: Option<u32>, reason: Rejection) {
+        if let Some(id) = id {
+            if self.pending.remove(&id) {
+                self.events.push_back(Event::Rejected { id, reason });
+            }
+        }
+    }
+
+    fn respond(&mut self, id: u32, name: &str, buffer: Vec<u8>) -> Result<(), CallError> {
+        let message = RpcMessage {
+            r#type: Type::Response as i32,
+            id: Some(id),
+            name: Some(name.to_string()),
+            buffer: Some(buffer),
+        };
+
+        self.send_message(message)
+    }
+
+    fn reply(&mut self, r#type: Type, id: u32, buffer: Option<Vec<u8>>) -> Result<(), CallError> {
+        let message = RpcMessage {
+            r#type: r#type as i32,
+            id: Some(id),
+            name: None,
+            buffer,
+        };
+
+        self.send_message(message)
+    }
+
+    /// Take the next event produced while processing received messages.
+    pub fn poll_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    /// Bytes queued for sending to OLA. After (partially) writing them out,
+    /// call [`advance_transmit`](Self::advance_transmit) with the number of
+    /// bytes written.
+    pub fn transmit(&self) -> &[u8] {
+        &self.transmit
+    }
+
+    /// Remove `count` bytes from the front of the transmit buffer.
+    pub fn advance_transmit(&mut self, count: usize) {
+        self.transmit.advance(count);
+    }
+
+    /// Queue a call to OLA, returning its sequence number. Unless the call is
+    /// streamed, OLA will answer it with an [`Event::Response`] (or an
+    /// [`Event::Rejected`]) carrying the same sequence number.
+    pub fn call(&mut self, request: OlaServerServiceCall) -> Result<u32, CallError> {
+        let id = self.ctx.next_sequence();
+        let message = request.to_message(id);
+        if message.r#type() == Type::Request {
+            self.pending.insert(id);
+        }
+
+        self.send_message(message)?;
+
+        Ok(id)
+    }
+
+    /// Cancel a call made with [`call`](Self::call), telling OLA it no longer
+    /// needs to be answered. Any response arriving afterwards is ignored.
+    /// Returns whether the call was still waiting for a response.
+    pub fn cancel(&mut self, id: u32) -> Result<bool, CallError> {
+        if !self.pending.remove(&id) {
+            return Ok(false);
+        }
+
+        self.reply(Type::RequestCancel, id, None)?;
+
+        Ok(true)
+    }
+
+    /// Queue a raw RPC message for sending to OLA.
+    pub fn send_message(&mut self, message: RpcMessage) -> Result<(), CallError> {
+        self.ctx
+            .encode_message(message, &mut self.transmit)
+            .map_err(|e| CallError {
+                kind: CallErrorKind::Encode(e),
+            })
+    }
+
+    /// Whether the call with the given sequence number is still waiting for
+    /// a response.
+    pub fn is_pending(&self, id: u32) -> bool {
+        self.pending.contains(&id)
+    }
+
+    /// Queue a DMX buffer to be streamed to an OLA universe.
+    pub fn send_dmx(
+        &mut self,
+        universe: u32,
+        data: &DmxBuffer,
+        priority: u8,
+    ) -> Result<(), CallError> {
+        self.ctx
+            .encode_stream_dmx(universe, data.as_slice(), priority, &mut self.transmit);
+
+        Ok(())
+    }
+
+    /// Queue a DMX packet to be streamed to an OLA universe. OLA only
+    /// transports null start code packets, so any other start code fails
+    /// with [`CallErrorKind::UnsupportedStartCode`] without queueing
+    /// anything.
+    ///
+    /// ```
+    /// # use ola::client::CallErrorKind;
+    /// # use ola::packet::{Packet, StartCode};
+    /// # use ola::session::Session;
+    /// # use ola::DmxBuffer;
+    /// let mut session = Session::new();
+    ///
+    /// let text = Packet::new(StartCode(0x17), DmxBuffer::new());
+    /// let error = session.send_packet(1, &text, 100).unwrap_err();
+    /// assert!(matches!(
+    ///     error.kind,
+    ///     CallErrorKind::UnsupportedStartCode(StartCode(0x17))
+    /// ));
+    /// assert!(session.transmit().is_empty());
+    /// ```
+    pub fn send_packet(
+        &mut self,
+        universe: u32,
+        packet: &Packet,
+        priority: u8,
+    ) -> Result<(), CallError> {
+        if !packet.start_code().is_null() {
+            return Err(CallError {
+                kind: CallErrorKind::UnsupportedStartCode(packet.start_code()),
+            });
+        }
+
+        self.send_dmx(universe, packet.data(), priority)
+    }
+
+    /// Queue a call registering for updates to a universe, returning its
+    /// sequence number.
+    pub fn register_universe(&mut self, universe: u32) -> Result<u32, CallError> {
+        self.call(OlaServerServiceCall::RegisterForDmx(RegisterDmxRequest {
+            universe: universe as i32,
+            action: RegisterAction::Register as i32,
+        }))
+    }
+
+    /// Queue a call unregistering from updates to a universe, returning its
+    /// sequence number.
+    pub fn unregister_universe(&mut self, universe: u32) -> Result<u32, CallError> {
+        self.call(OlaServerServiceCall::RegisterForDmx(RegisterDmxRequest {
+            universe: universe as i32,
+            action: RegisterAction::Unregister as i32,
+        }))
+    }
+}
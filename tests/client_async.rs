@@ -0,0 +1,361 @@
+#![cfg(feature = "tokio")]
+
+use std::time::Duration;
+
+use bytes::BytesMut;
+use ola::client::{CallErrorKind, ClientAsync};
+use ola::ola::proto::rpc::{RpcMessage, Type};
+use ola::ola::proto::{
+    Ack, DmxData, OlaServerServiceCall, OptionalUniverseRequest, RegisterAction,
+    RegisterDmxRequest, UniverseInfo, UniverseInfoReply,
+};
+use ola::ola::{encode_header, RpcContext};
+use ola::session::Rejection;
+use ola::DmxBuffer;
+use prost::Message;
+use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+/// The `olad` end of a connection, scripted by the test.
+struct Olad {
+    stream: DuplexStream,
+    ctx: RpcContext,
+    received: BytesMut,
+}
+
+impl Olad {
+    /// The next message sent by the client, or `None` once it closed the
+    /// connection.
+    async fn next(&mut self) -> Option<RpcMessage> {
+        loop {
+            if let Some(message) = self.ctx.decode_frame(&mut self.received).unwrap() {
+                return Some(message);
+            }
+            if self.stream.read_buf(&mut self.received).await.unwrap() == 0 {
+                return None;
+            }
+        }
+    }
+
+    async fn send(&mut self, message: RpcMessage) {
+        let mut frame = BytesMut::new();
+        self.ctx.encode_message(message, &mut frame).unwrap();
+        self.stream.write_all(&frame).await.unwrap();
+    }
+
+    /// Answer the call with the given sequence number.
+    async fn respond(&mut self, id: u32, response: impl Message) {
+        self.send(RpcMessage {
+            r#type: Type::Response as i32,
+            id: Some(id),
+            name: None,
+            buffer: Some(response.encode_to_vec()),
+        })
+        .await;
+    }
+
+    /// The next message sent by the client, expecting it to call `method`.
+    async fn expect<R: Message + Default>(&mut self, r#type: Type, method: &str) -> (u32, R) {
+        let message = self.next().await.expect("connection closed");
+        assert_eq!(message.r#type(), r#type);
+        assert_eq!(message.name.as_deref(), Some(method));
+        let request = R::decode(message.buffer.unwrap_or_default().as_slice()).unwrap();
+
+        (message.id.unwrap_or_default(), request)
+    }
+}
+
+fn connect() -> (ClientAsync, Olad) {
+    connect_with_buffer(4096)
+}
+
+fn connect_with_buffer(size: usize) -> (ClientAsync, Olad) {
+    let (client, olad) = duplex(size);
+    let olad = Olad {
+        stream: olad,
+        ctx: RpcContext::new(),
+        received: BytesMut::new(),
+    };
+
+    (ClientAsync::from_stream(client), olad)
+}
+
+#[tokio::test]
+async fn eof_disconnects_recv_and_send() {
+    let (client, olad) = connect();
+    drop(olad);
+
+    let error = client.recv().await.unwrap_err();
+    assert!(matches!(error.kind, CallErrorKind::Disconnected));
+
+    let error = client
+        .send_dmx_streaming(1, &DmxBuffer::new())
+        .await
+        .unwrap_err();
+    assert!(matches!(error.kind, CallErrorKind::Disconnected));
+}
+
+#[tokio::test]
+async fn disconnect_fails_waiting_recv() {
+    let (client, mut olad) = connect();
+    let recv = tokio::spawn({
+        let client = client.clone();
+        async move { client.recv().await }
+    });
+
+    olad.send(RpcMessage {
+        r#type: Type::Disconnect as i32,
+        id: None,
+        name: None,
+        buffer: None,
+    })
+    .await;
+    assert!(olad.next().await.is_none());
+
+    let error = recv.await.unwrap().unwrap_err();
+    assert!(matches!(error.kind, CallErrorKind::Disconnected));
+}
+
+#[tokio::test]
+async fn close_blacks_out_and_unregisters() {
+    let (client, mut olad) = connect();
+
+    let register = tokio::spawn({
+        let client = client.clone();
+        async move { client.register_universe(1).await }
+    });
+    let (id, request) = olad
+        .expect::<RegisterDmxRequest>(Type::Request, "RegisterForDmx")
+        .await;
+    assert_eq!(request.universe, 1);
+    assert_eq!(request.action(), RegisterAction::Register);
+    olad.respond(id, Ack {}).await;
+    register.await.unwrap().unwrap();
+
+    let mut data = DmxBuffer::new();
+    data.set_channel(1, 255);
+    client
+        .send_dmx_streaming_with_priority(2, &data, 50)
+        .await
+        .unwrap();
+    let (_, frame) = olad
+        .expect::<DmxData>(Type::StreamRequest, "StreamDmxData")
+        .await;
+    assert_eq!(frame.data[0], 255);
+
+    let close = tokio::spawn(client.close(true));
+
+    let (_, blackout) = olad
+        .expect::<DmxData>(Type::StreamRequest, "StreamDmxData")
+        .await;
+    assert_eq!(blackout.universe, 2);
+    assert_eq!(blackout.priority, Some(50));
+    assert!(blackout.data.iter().all(|&value| value == 0));
+
+    let (_, request) = olad
+        .expect::<RegisterDmxRequest>(Type::Request, "RegisterForDmx")
+        .await;
+    assert_eq!(request.universe, 1);
+    assert_eq!(request.action(), RegisterAction::Unregister);
+
+    assert!(olad.next().await.is_none());
+    close.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn rejected_calls_fail_with_reason() {
+    let (client, mut olad) = connect();
+
+    let mut calls = Vec::new();
+    for _ in 0..3 {
+        let request = OptionalUniverseRequest { universe: None };
+        let call = client
+            .call::<UniverseInfoReply>(OlaServerServiceCall::GetUniverseInfo(request))
+            .unwrap();
+        calls.push(tokio::spawn(call));
+    }
+
+    let mut ids = Vec::new();
+    for _ in 0..3 {
+        let (id, _) = olad
+            .expect::<OptionalUniverseRequest>(Type::Request, "GetUniverseInfo")
+            .await;
+        ids.push(id);
+    }
+    let replies = [
+        (Type::ResponseNotImplemented, None),
+        (Type::ResponseFailed, Some(b"no such universe".to_vec())),
+        (Type::ResponseCancel, None),
+    ];
+    for (id, (r#type, buffer)) in ids.into_iter().zip(replies) {
+        olad.send(RpcMessage {
+            r#type: r#type as i32,
+            id: Some(id),
+            name: None,
+            buffer,
+        })
+        .await;
+    }
+
+    let expected = [
+        Rejection::NotImplemented,
+        Rejection::Failed(String::from("no such universe")),
+        Rejection::Cancelled,
+    ];
+    for (call, expected) in calls.into_iter().zip(expected) {
+        match call.await.unwrap().unwrap_err().kind {
+            CallErrorKind::Rejected(reason) => assert_eq!(reason, expected),
+            kind => panic!("unexpected error: {kind:?}"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn unanswerable_requests_are_not_implemented() {
+    let (_client, mut olad) = connect();
+
+    olad.send(RpcMessage {
+        r#type: Type::DescriptorRequest as i32,
+        id: Some(7),
+        name: None,
+        buffer: None,
+    })
+    .await;
+    olad.send(RpcMessage {
+        r#type: Type::Request as i32,
+        id: Some(8),
+        name: Some(String::from("NewMethod")),
+        buffer: Some(Vec::new()),
+    })
+    .await;
+
+    for id in [7, 8] {
+        let reply = olad.next().await.unwrap();
+        assert_eq!(reply.r#type(), Type::ResponseNotImplemented);
+        assert_eq!(reply.id, Some(id));
+    }
+}
+
+#[tokio::test]
+async fn dropped_call_is_cancelled() {
+    let (client, mut olad) = connect();
+
+    let request = OptionalUniverseRequest { universe: None };
+    let call = client
+        .call::<UniverseInfoReply>(OlaServerServiceCall::GetUniverseInfo(request.clone()))
+        .unwrap();
+    let (id, _) = olad
+        .expect::<OptionalUniverseRequest>(Type::Request, "GetUniverseInfo")
+        .await;
+
+    drop(call);
+    let cancel = olad.next().await.unwrap();
+    assert_eq!(cancel.r#type(), Type::RequestCancel);
+    assert_eq!(cancel.id, Some(id));
+
+    // a late response to the cancelled call is ignored
+    olad.respond(id, UniverseInfoReply::default()).await;
+
+    let call = client
+        .call::<UniverseInfoReply>(OlaServerServiceCall::GetUniverseInfo(request))
+        .unwrap();
+    let (next, _) = olad
+        .expect::<OptionalUniverseRequest>(Type::Request, "GetUniverseInfo")
+        .await;
+    assert_ne!(next, id);
+    call.cancel().await.unwrap();
+
+    let cancel = olad.next().await.unwrap();
+    assert_eq!(cancel.r#type(), Type::RequestCancel);
+    assert_eq!(cancel.id, Some(next));
+}
+
+fn universe_info(universe: i32) -> UniverseInfoReply {
+    UniverseInfoReply {
+        universe: vec![UniverseInfo {
+            universe,
+            ..Default::default()
+        }],
+    }
+}
+
+#[tokio::test]
+async fn responses_are_routed_by_sequence_number() {
+    let (client, mut olad) = connect();
+
+    let mut calls = Vec::new();
+    for universe in [1, 2] {
+        let request = OptionalUniverseRequest {
+            universe: Some(universe),
+        };
+        let call = client
+            .call::<UniverseInfoReply>(OlaServerServiceCall::GetUniverseInfo(request))
+            .unwrap();
+        calls.push(tokio::spawn(call));
+    }
+
+    let mut requests = Vec::new();
+    for _ in 0..2 {
+        requests.push(
+            olad.expect::<OptionalUniverseRequest>(Type::Request, "GetUniverseInfo")
+                .await,
+        );
+    }
+    // answer in the opposite order
+    for (id, request) in requests.into_iter().rev() {
+        olad.respond(id, universe_info(request.universe.unwrap()))
+            .await;
+    }
+
+    for (call, universe) in calls.into_iter().zip([1, 2]) {
+        let reply = call.await.unwrap().unwrap();
+        assert_eq!(reply.universe[0].universe, universe);
+    }
+}
+
+#[tokio::test]
+async fn undecodable_messages_are_skipped() {
+    let (client, mut olad) = connect();
+
+    let request = OptionalUniverseRequest { universe: None };
+    let call = client
+        .call::<UniverseInfoReply>(OlaServerServiceCall::GetUniverseInfo(request))
+        .unwrap();
+    let (id, _) = olad
+        .expect::<OptionalUniverseRequest>(Type::Request, "GetUniverseInfo")
+        .await;
+
+    // a frame which isn't a valid RPC message
+    let garbage = [0xff; 3];
+    olad.stream
+        .write_all(&encode_header(1, garbage.len()))
+        .await
+        .unwrap();
+    olad.stream.write_all(&garbage).await.unwrap();
+    olad.respond(id, universe_info(3)).await;
+
+    assert_eq!(call.await.unwrap().universe[0].universe, 3);
+}
+
+#[tokio::test]
+async fn close_timeout_drops_connection() {
+    // too small for the blackout frame, which olad never reads
+    let (client, _olad) = connect_with_buffer(64);
+    client.set_call_timeout(Some(Duration::from_millis(50)));
+
+    let error = client
+        .send_dmx_streaming(1, &DmxBuffer::new())
+        .await
+        .unwrap_err();
+    assert!(matches!(error.kind, CallErrorKind::Timeout));
+
+    let recv = tokio::spawn({
+        let client = client.clone();
+        async move { client.recv().await }
+    });
+
+    let error = client.close(true).await.unwrap_err();
+    assert!(matches!(error.kind, CallErrorKind::Timeout));
+
+    let error = recv.await.unwrap().unwrap_err();
+    assert!(matches!(error.kind, CallErrorKind::Disconnected));
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "codec")]
+
+use futures_util::{SinkExt, StreamExt};
+use ola::codec::{ClientCodec, Item, ServerCodec};
+use ola::ola::proto::rpc::{RpcMessage, Type};
+use ola::ola::proto::{
+    Ack, DmxData, OlaClientServiceCall, OlaServerServiceCall, RegisterAction, RegisterDmxRequest,
+};
+use prost::Message;
+use tokio::io::duplex;
+use tokio_util::codec::Framed;
+
+#[tokio::test]
+async fn response_frames_are_passed_through() -> anyhow::Result<()> {
+    let (client, server) = duplex(1024);
+    let mut client = Framed::new(client, ClientCodec::new());
+    let mut server = Framed::new(server, ServerCodec::new());
+
+    client
+        .send(OlaServerServiceCall::RegisterForDmx(RegisterDmxRequest {
+            universe: 1,
+            action: RegisterAction::Register as i32,
+        }))
+        .await?;
+    let id = match server.next().await.unwrap()? {
+        Item::Call(id, OlaServerServiceCall::RegisterForDmx(request)) => {
+            assert_eq!(request.universe, 1);
+            id
+        }
+        item => panic!("unexpected item: {item:?}"),
+    };
+
+    let response = RpcMessage {
+        r#type: Type::Response as i32,
+        id: Some(id),
+        name: None,
+        buffer: Some(Ack {}.encode_to_vec()),
+    };
+    server.send(response.clone()).await?;
+    let rejection = RpcMessage {
+        r#type: Type::ResponseNotImplemented as i32,
+        id: Some(id + 1),
+        name: None,
+        buffer: None,
+    };
+    server.send(rejection.clone()).await?;
+    server
+        .send(RpcMessage {
+            r#type: Type::Request as i32,
+            id: Some(0),
+            name: Some(String::from("UpdateDmxData")),
+            buffer: Some(
+                DmxData {
+                    universe: 1,
+                    data: vec![255],
+                    priority: None,
+                }
+                .encode_to_vec(),
+            ),
+        })
+        .await?;
+
+    match client.next().await.unwrap()? {
+        Item::Message(message) => assert_eq!(message, response),
+        item => panic!("unexpected item: {item:?}"),
+    }
+    match client.next().await.unwrap()? {
+        Item::Message(message) => assert_eq!(message, rejection),
+        item => panic!("unexpected item: {item:?}"),
+    }
+    // the stream carries on after the non-request messages
+    match client.next().await.unwrap()? {
+        Item::Call(_, OlaClientServiceCall::UpdateDmxData(data)) => {
+            assert_eq!(data.data, [255]);
+        }
+        item => panic!("unexpected item: {item:?}"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn unknown_requests_are_passed_through() -> anyhow::Result<()> {
+    let (client, server) = duplex(1024);
+    let mut client = Framed::new(client, ClientCodec::new());
+    let mut server = Framed::new(server, ServerCodec::new());
+
+    let request = RpcMessage {
+        r#type: Type::Request as i32,
+        id: Some(7),
+        name: Some(String::from("GetPlugins")),
+        buffer: Some(Vec::new()),
+    };
+    server.send(request.clone()).await?;
+
+    match client.next().await.unwrap()? {
+        Item::Message(message) => assert_eq!(message, request),
+        item => panic!("unexpected item: {item:?}"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use std::time::{Duration, Instant};
+
+use ola::merge::{MergeMode, Merger, Priority};
+use ola::DmxBuffer;
+
+fn buffer(values: &[u8]) -> DmxBuffer {
+    let mut buffer = DmxBuffer::with_slots(values.len());
+    buffer.as_mut_slice().copy_from_slice(values);
+    buffer
+}
+
+#[test]
+fn htp_takes_highest_value_of_top_priority() {
+    let mut merger = Merger::new(MergeMode::Htp);
+    merger.update("a", &buffer(&[10, 200, 30]), 100);
+    merger.update("b", &buffer(&[50, 20, 30, 40]), 100);
+    merger.update("low", &buffer(&[255, 255, 255, 255, 255]), 50);
+
+    assert_eq!(merger.merge().unwrap().as_slice(), &[50, 200, 30, 40]);
+}
+
+#[test]
+fn ltp_takes_latest_source_of_top_priority() {
+    let start = Instant::now();
+    let mut merger = Merger::new(MergeMode::Ltp);
+    merger.update_at(
+        "late",
+        &buffer(&[1, 2]),
+        100,
+        start + Duration::from_secs(2),
+    );
+    merger.update_at("early", &buffer(&[3, 4]), 100, start);
+    merger.update_at("low", &buffer(&[5, 6]), 50, start + Duration::from_secs(3));
+
+    assert_eq!(merger.merge().unwrap().as_slice(), &[1, 2]);
+}
+
+#[test]
+fn ltp_ties_go_to_last_update() {
+    let now = Instant::now();
+    for keys in [["a", "b", "c"], ["c", "b", "a"], ["b", "c", "a"]] {
+        let mut merger = Merger::new(MergeMode::Ltp);
+        for (value, key) in keys.into_iter().enumerate() {
+            merger.update_at(key, &buffer(&[value as u8]), 100, now);
+        }
+
+        assert_eq!(merger.merge().unwrap().as_slice(), &[2]);
+    }
+}
+
+#[test]
+fn ltp_channel_ties_go_to_last_update() {
+    let now = Instant::now();
+    let mut merger = Merger::new(MergeMode::Ltp);
+    for (value, key) in ["a", "b", "c"].into_iter().enumerate() {
+        let priorities = Priority::Channel(buffer(&[100, 100]));
+        merger.update_at(key, &buffer(&[value as u8, 10]), priorities, now);
+    }
+    merger.update_at(
+        "a",
+        &buffer(&[20, 20]),
+        Priority::Channel(buffer(&[0, 100])),
+        now,
+    );
+
+    assert_eq!(merger.merge().unwrap().as_slice(), &[2, 20]);
+}
+
+#[test]
+fn universe_priority_zero_is_merged() {
+    let mut merger = Merger::new(MergeMode::Htp);
+    merger.update("a", &buffer(&[10, 20]), 0);
+    assert_eq!(merger.merge().unwrap().as_slice(), &[10, 20]);
+
+    merger.update("b", &buffer(&[30]), 0);
+    assert_eq!(merger.merge().unwrap().as_slice(), &[30, 20]);
+
+    merger.update("c", &buffer(&[1]), 1);
+    assert_eq!(merger.merge().unwrap().as_slice(), &[1]);
+}
+
+#[test]
+fn channel_priority_zero_is_unset() {
+    let mut merger = Merger::new(MergeMode::Htp);
+    merger.update("parked", &buffer(&[10, 20]), 0);
+    merger.update(
+        "console",
+        &buffer(&[200, 200]),
+        Priority::Channel(buffer(&[0, 1])),
+    );
+
+    // channel 1 isn't driven by the console, leaving it to the source at
+    // universe priority 0
+    assert_eq!(merger.merge().unwrap().as_slice(), &[10, 200]);
+
+    merger.remove(&"parked");
+    assert_eq!(merger.merge().unwrap().as_slice(), &[0, 200]);
+}
+
+#[test]
+fn empty_merger_merges_nothing() {
+    let mut merger = Merger::<&str>::default();
+    assert!(merger.merge().is_none());
+
+    merger.update("a", &DmxBuffer::new(), 0);
+    merger.clear();
+    assert!(merger.merge().is_none());
+}
@@ -0,0 +1,114 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::ExitStatusExt;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use ola::config::{Backoff, Config};
+use ola::olad::SupervisorEvent;
+
+const STUBS: [(&str, &str); 3] = [
+    // exits on its own shortly after starting
+    ("crashing", "sleep 1.5\nexit 3\n"),
+    // exits cleanly when asked to
+    (
+        "stopping",
+        "trap 'exit 0' TERM\nwhile :; do sleep 0.05; done\n",
+    ),
+    // has to be killed
+    ("stubborn", "trap '' TERM\nwhile :; do sleep 0.05; done\n"),
+];
+
+/// Path of a stub `olad` script. The scripts are all written before any is
+/// run, as a script still open for writing in one test can't be executed by
+/// another.
+fn stub(name: &str) -> PathBuf {
+    static DIR: OnceLock<PathBuf> = OnceLock::new();
+    let dir = DIR.get_or_init(|| {
+        let dir = std::env::temp_dir().join(format!("ola-olad-stubs-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (name, script) in STUBS {
+            let path = dir.join(name);
+            fs::write(&path, format!("#!/bin/sh\n{script}")).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        dir
+    });
+
+    dir.join(name)
+}
+
+fn config(stub_name: &str) -> Config {
+    Config {
+        auto_start: false,
+        olad_path: stub(stub_name),
+        kill_on_drop: true,
+        restart_backoff: Backoff {
+            initial: Duration::from_millis(10),
+            max: Duration::from_millis(100),
+            multiplier: 2,
+            max_attempts: None,
+        },
+        ..Config::new()
+    }
+}
+
+fn is_running(pid: u32) -> bool {
+    // SAFETY: signal 0 only checks whether the process exists
+    unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+}
+
+#[test]
+fn supervisor_restarts_crashed_olad() {
+    let supervisor = config("crashing").supervise().unwrap();
+    let events = supervisor.subscribe();
+    let first = supervisor.id().unwrap();
+    assert_eq!(supervisor.generation(), 0);
+
+    let timeout = Duration::from_secs(5);
+    match events.recv_timeout(timeout).unwrap() {
+        SupervisorEvent::Exited(status) => assert_eq!(status.code(), Some(3)),
+        event => panic!("unexpected event: {event:?}"),
+    }
+    match events.recv_timeout(timeout).unwrap() {
+        SupervisorEvent::Restarted => {}
+        event => panic!("unexpected event: {event:?}"),
+    }
+
+    assert_eq!(supervisor.generation(), 1);
+    let second = supervisor.id().unwrap();
+    assert_ne!(second, first);
+
+    supervisor.shutdown(Duration::from_secs(1)).unwrap();
+    assert!(!is_running(second));
+}
+
+#[test]
+fn shutdown_terminates_olad() {
+    let mut olad = config("stopping").launch().unwrap();
+
+    let status = olad.shutdown(Duration::from_secs(5)).unwrap();
+    assert_eq!(status.code(), Some(0));
+}
+
+#[test]
+fn shutdown_kills_unresponsive_olad() {
+    let mut olad = config("stubborn").launch().unwrap();
+
+    let status = olad.shutdown(Duration::from_millis(200)).unwrap();
+    assert_eq!(status.signal(), Some(libc::SIGKILL));
+}
+
+#[test]
+fn dropped_handle_kills_olad() {
+    let olad = config("stubborn").launch().unwrap();
+    let pid = olad.id();
+    assert!(is_running(pid));
+
+    drop(olad);
+    assert!(!is_running(pid));
+}
@@ -0,0 +1,136 @@
+#![cfg(feature = "serde")]
+
+use ola::merge::Priority;
+use ola::ola::proto::{DmxData, UniverseInfo, UniverseInfoReply};
+use ola::session::{Event, Rejection};
+use ola::DmxBuffer;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Scene {
+    #[serde(with = "ola::channel_map")]
+    universe: DmxBuffer,
+}
+
+#[test]
+fn buffer_binary_round_trip() {
+    let universe = DmxBuffer::try_from(vec![255, 0, 128]).unwrap();
+
+    let bytes = bincode::serialize(&universe).unwrap();
+    // a length prefix followed by the raw channel values
+    assert_eq!(&bytes[bytes.len() - 3..], [255, 0, 128]);
+    assert_eq!(bincode::deserialize::<DmxBuffer>(&bytes).unwrap(), universe);
+
+    let full = DmxBuffer::try_from(vec![7; 512]).unwrap();
+    let bytes = bincode::serialize(&full).unwrap();
+    assert_eq!(bincode::deserialize::<DmxBuffer>(&bytes).unwrap(), full);
+}
+
+#[test]
+fn buffer_binary_rejects_invalid_lengths() {
+    let empty = bincode::serialize(&Vec::<u8>::new()).unwrap();
+    assert!(bincode::deserialize::<DmxBuffer>(&empty).is_err());
+
+    let oversized = bincode::serialize(&vec![0u8; 513]).unwrap();
+    assert!(bincode::deserialize::<DmxBuffer>(&oversized).is_err());
+}
+
+#[test]
+fn buffer_json_rejects_invalid_lengths() {
+    assert!(serde_json::from_str::<DmxBuffer>(r#""""#).is_err());
+    assert!(serde_json::from_str::<DmxBuffer>("[]").is_err());
+
+    let oversized = serde_json::to_string(&vec![0u8; 513]).unwrap();
+    assert!(serde_json::from_str::<DmxBuffer>(&oversized).is_err());
+}
+
+#[test]
+fn channel_map_round_trip() {
+    let mut universe = DmxBuffer::with_slots(24);
+    universe.set_channel(1, 255);
+    universe.set_channel(24, 1);
+    let scene = Scene { universe };
+
+    let json = serde_json::to_string(&scene).unwrap();
+    assert_eq!(serde_json::from_str::<Scene>(&json).unwrap(), scene);
+
+    let bytes = bincode::serialize(&scene).unwrap();
+    assert_eq!(bincode::deserialize::<Scene>(&bytes).unwrap(), scene);
+}
+
+#[test]
+fn channel_map_rejects_invalid_buffers() {
+    let empty = r#"{"universe":{"slots":0,"channels":{}}}"#;
+    assert!(serde_json::from_str::<Scene>(empty).is_err());
+
+    let oversized = r#"{"universe":{"slots":513,"channels":{}}}"#;
+    assert!(serde_json::from_str::<Scene>(oversized).is_err());
+
+    let outside = r#"{"universe":{"slots":24,"channels":{"25":1}}}"#;
+    assert!(serde_json::from_str::<Scene>(outside).is_err());
+}
+
+#[test]
+fn event_round_trip() {
+    let events = [
+        Event::Dmx(DmxData {
+            universe: 1,
+            data: vec![255, 0, 128],
+            priority: Some(100),
+        }),
+        Event::Response {
+            id: 3,
+            buffer: vec![1, 2],
+        },
+        Event::Rejected {
+            id: 4,
+            reason: Rejection::Failed("no such universe".into()),
+        },
+        Event::Disconnected,
+    ];
+
+    for event in events {
+        let json: Event = serde_json::from_str(&serde_json::to_string(&event).unwrap()).unwrap();
+        let binary: Event = bincode::deserialize(&bincode::serialize(&event).unwrap()).unwrap();
+
+        // events don't implement PartialEq, so compare their debug output
+        assert_eq!(format!("{json:?}"), format!("{event:?}"));
+        assert_eq!(format!("{binary:?}"), format!("{event:?}"));
+    }
+}
+
+#[test]
+fn message_round_trip() {
+    let reply = UniverseInfoReply {
+        universe: vec![UniverseInfo {
+            universe: 1,
+            name: "stage".into(),
+            merge_mode: 1,
+            output_port_count: 2,
+            ..Default::default()
+        }],
+    };
+
+    let json = serde_json::to_string(&reply).unwrap();
+    assert_eq!(
+        serde_json::from_str::<UniverseInfoReply>(&json).unwrap(),
+        reply
+    );
+
+    let bytes = bincode::serialize(&reply).unwrap();
+    assert_eq!(
+        bincode::deserialize::<UniverseInfoReply>(&bytes).unwrap(),
+        reply
+    );
+}
+
+#[test]
+fn priority_round_trip() {
+    let mut priorities = DmxBuffer::new();
+    priorities.set_channel(2, 100);
+
+    for priority in [Priority::Universe(0), Priority::Channel(priorities)] {
+        let bytes = bincode::serialize(&priority).unwrap();
+        assert_eq!(bincode::deserialize::<Priority>(&bytes).unwrap(), priority);
+    }
+}
@@ -0,0 +1,60 @@
+#![cfg(unix)]
+
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+
+use bytes::BytesMut;
+use ola::client::StreamingClient;
+use ola::ola::proto::rpc::{RpcMessage, Type};
+use ola::ola::proto::Ack;
+use ola::ola::{encode_header, RpcContext};
+use prost::Message;
+
+/// Read the next message sent by the client.
+fn next_message(stream: &mut UnixStream, ctx: &mut RpcContext, buf: &mut BytesMut) -> RpcMessage {
+    loop {
+        if let Some(message) = ctx.decode_frame(buf).unwrap() {
+            return message;
+        }
+        let mut chunk = [0; 1024];
+        let read = stream.read(&mut chunk).unwrap();
+        assert!(read > 0, "client closed the connection");
+        buf.extend_from_slice(&chunk[..read]);
+    }
+}
+
+#[test]
+fn register_waits_past_undecodable_messages() -> anyhow::Result<()> {
+    let (client, mut olad) = UnixStream::pair()?;
+    let server = std::thread::spawn(move || {
+        let mut ctx = RpcContext::new();
+        let mut buf = BytesMut::new();
+        let request = next_message(&mut olad, &mut ctx, &mut buf);
+        assert_eq!(request.name.as_deref(), Some("RegisterForDmx"));
+
+        // a frame which isn't a valid RPC message
+        let garbage = [0xff; 3];
+        olad.write_all(&encode_header(1, garbage.len()))?;
+        olad.write_all(&garbage)?;
+
+        let mut response = BytesMut::new();
+        ctx.encode_message(
+            RpcMessage {
+                r#type: Type::Response as i32,
+                id: request.id,
+                name: None,
+                buffer: Some(Ack {}.encode_to_vec()),
+            },
+            &mut response,
+        )?;
+        olad.write_all(&response)?;
+
+        anyhow::Ok(olad)
+    });
+
+    let mut client = StreamingClient::from_stream(client);
+    client.register_universe(1)?;
+    let _olad = server.join().unwrap()?;
+
+    Ok(())
+}